@@ -0,0 +1,20 @@
+use crate::math::Vector3;
+
+use super::Background;
+
+/// The same color in every direction
+pub struct Constant {
+    color: Vector3,
+}
+
+impl Constant {
+    pub fn new(color: Vector3) -> Self {
+        Self { color }
+    }
+}
+
+impl Background for Constant {
+    fn value(&self, _direction: &Vector3) -> Vector3 {
+        self.color.clone()
+    }
+}
@@ -0,0 +1,156 @@
+use std::{f64::consts::PI, fs::File, io::BufReader, path::Path};
+
+use image::{
+    codecs::hdr::{HdrDecoder, HdrMetadata},
+    ImageResult, Rgb32FImage,
+};
+
+use crate::math::{self, Vector3};
+
+use super::{Background, BackgroundSample};
+
+/// Latitude-longitude (equirectangular) HDR image around the scene, with the top row
+/// straight up and the middle column along -z. Directions are picked in proportion to
+/// the luminance of each pixel over the solid angle it covers.
+pub struct EnvironmentMap {
+    width: usize,
+    height: usize,
+    pixels: Vec<Vector3>,
+    /// Around the vertical axis, in radians
+    rotation: f64,
+    /// Running sum of the sampling weight of the rows
+    rows: Vec<f64>,
+    /// Running sum of the sampling weight of the pixels, restarting every row
+    columns: Vec<f64>,
+}
+
+impl EnvironmentMap {
+    /// `.hdr` or `.exr` file, `rotation` in radians
+    pub fn open(file: &str, rotation: f64, intensity: f64) -> ImageResult<Self> {
+        let radiance = Path::new(file)
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("hdr"));
+        let image = if radiance {
+            // the generic loader turns Radiance files into 8 bit colors
+            let decoder = HdrDecoder::new(BufReader::new(File::open(file)?))?;
+            let HdrMetadata { width, height, .. } = decoder.metadata();
+            let pixels = decoder.read_image_hdr()?.into_iter().flat_map(|p| p.0);
+            Rgb32FImage::from_raw(width, height, pixels.collect()).expect("same dimensions")
+        } else {
+            image::open(file)?.into_rgb32f()
+        };
+        Ok(Self::from_image(image, rotation, intensity))
+    }
+
+    pub fn from_image(image: Rgb32FImage, rotation: f64, intensity: f64) -> Self {
+        let (width, height) = (image.width() as usize, image.height() as usize);
+        let pixels: Vec<Vector3> = image
+            .pixels()
+            .map(|p| Vector3::new(p.0[0] as f64, p.0[1] as f64, p.0[2] as f64) * intensity)
+            .collect();
+
+        let mut rows = Vec::with_capacity(height);
+        let mut columns = Vec::with_capacity(width * height);
+        let mut total = 0.0;
+        for (row, line) in pixels.chunks(width).enumerate() {
+            // rows near the poles cover less of the sphere
+            let sin_theta = (PI * (row as f64 + 0.5) / height as f64).sin();
+            let mut sum = 0.0;
+            for pixel in line {
+                sum += pixel.luminance().max(0.0) * sin_theta;
+                columns.push(sum);
+            }
+            total += sum;
+            rows.push(total);
+        }
+
+        Self {
+            width,
+            height,
+            pixels,
+            rotation,
+            rows,
+            columns,
+        }
+    }
+
+    fn pixel(&self, direction: &Vector3) -> (usize, usize) {
+        let direction = direction.normal();
+        let phi = direction.x.atan2(-direction.z) - self.rotation;
+        let u = (0.5 + phi / (2.0 * PI)).rem_euclid(1.0);
+        let v = direction.y.clamp(-1.0, 1.0).acos() / PI;
+        let column = ((u * self.width as f64) as usize).min(self.width - 1);
+        let row = ((v * self.height as f64) as usize).min(self.height - 1);
+        (row, column)
+    }
+
+    /// Sampling weight of a pixel
+    fn weight(&self, row: usize, column: usize) -> f64 {
+        let index = row * self.width + column;
+        let before = if column > 0 {
+            self.columns[index - 1]
+        } else {
+            0.0
+        };
+        self.columns[index] - before
+    }
+
+    fn total(&self) -> f64 {
+        self.rows.last().copied().unwrap_or(0.0)
+    }
+}
+
+impl Background for EnvironmentMap {
+    fn value(&self, direction: &Vector3) -> Vector3 {
+        let (row, column) = self.pixel(direction);
+        self.pixels[row * self.width + column].clone()
+    }
+
+    fn sample(&self) -> Option<BackgroundSample> {
+        let total = self.total();
+        if total <= 0.0 {
+            return None;
+        }
+
+        let target = math::rand_f64() * total;
+        let row = self
+            .rows
+            .partition_point(|&sum| sum <= target)
+            .min(self.height - 1);
+        let line = &self.columns[row * self.width..(row + 1) * self.width];
+        let target = math::rand_f64() * line[self.width - 1];
+        let column = line
+            .partition_point(|&sum| sum <= target)
+            .min(self.width - 1);
+
+        // uniform within the pixel
+        let u = (column as f64 + math::rand_f64()) / self.width as f64;
+        let v = (row as f64 + math::rand_f64()) / self.height as f64;
+        let phi = (u - 0.5) * 2.0 * PI + self.rotation;
+        let theta = v * PI;
+        let sin_theta = theta.sin();
+        if sin_theta <= 0.0 {
+            return None;
+        }
+        let direction = Vector3::new(sin_theta * phi.sin(), theta.cos(), -sin_theta * phi.cos());
+
+        let probability = self.weight(row, column) / total;
+        Some(BackgroundSample {
+            direction,
+            radiance: self.pixels[row * self.width + column].clone(),
+            pdf: probability * (self.width * self.height) as f64 / (2.0 * PI * PI * sin_theta),
+        })
+    }
+
+    fn pdf(&self, direction: &Vector3) -> f64 {
+        let direction = direction.normal();
+        let total = self.total();
+        let sin_theta = (1.0 - direction.y * direction.y).max(0.0).sqrt();
+        if total <= 0.0 || sin_theta <= 0.0 {
+            return 0.0;
+        }
+        let (row, column) = self.pixel(&direction);
+        let probability = self.weight(row, column) / total;
+        probability * (self.width * self.height) as f64 / (2.0 * PI * PI * sin_theta)
+    }
+}
@@ -0,0 +1,22 @@
+use crate::math::Vector3;
+
+use super::Background;
+
+/// Blend from `bottom` straight down to `top` straight up, the sky from the book
+pub struct Gradient {
+    top: Vector3,
+    bottom: Vector3,
+}
+
+impl Gradient {
+    pub fn new(top: Vector3, bottom: Vector3) -> Self {
+        Self { top, bottom }
+    }
+}
+
+impl Background for Gradient {
+    fn value(&self, direction: &Vector3) -> Vector3 {
+        let a = 0.5 * (direction.normal().y + 1.0);
+        &self.bottom * (1.0 - a) + &self.top * a
+    }
+}
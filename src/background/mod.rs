@@ -0,0 +1,29 @@
+use crate::math::Vector3;
+
+pub mod constant;
+pub mod environment;
+pub mod gradient;
+pub mod sky;
+
+/// Direction towards the background picked by importance
+pub struct BackgroundSample {
+    /// Unit direction leaving the scene
+    pub direction: Vector3,
+    pub radiance: Vector3,
+    /// Density over the sphere of picking the direction
+    pub pdf: f64,
+}
+
+/// Radiance arriving from infinitely far away, seen by the rays leaving the scene
+pub trait Background: Send + Sync + 'static {
+    fn value(&self, direction: &Vector3) -> Vector3;
+    /// Backgrounds that light the scene are also sampled explicitly
+    fn sample(&self) -> Option<BackgroundSample> {
+        None
+    }
+    fn pdf(&self, _direction: &Vector3) -> f64 {
+        0.0
+    }
+}
+
+pub type BackgroundRef = Box<dyn Background>;
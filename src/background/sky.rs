@@ -0,0 +1,184 @@
+use std::f64::consts::PI;
+
+use image::Rgb32FImage;
+
+use crate::{light::directional::DirectionalLight, math::Vector3, spectrum};
+
+use super::{environment::EnvironmentMap, Background, BackgroundSample};
+
+/// Solar illuminance outside the atmosphere, in kilolux
+const SOLAR_ILLUMINANCE: f64 = 128.0;
+
+/// Perez distribution coefficients `A` to `E`
+type Perez = [f64; 5];
+
+/// Clear daylight sky from Preetham, Shirley and Smits 1999, with the zenith scaled
+/// to a luminance of `intensity`. The sun itself is a separate directional light.
+pub struct Sky {
+    model: Model,
+    turbidity: f64,
+    /// Zenith luminance in kcd/m² before scaling
+    zenith_luminance: f64,
+    /// Tabulated copy of the sky, only used to pick directions
+    table: EnvironmentMap,
+}
+
+struct Model {
+    sun: Vector3,
+    intensity: f64,
+    /// Zenith value and Perez coefficients of the luminance and the two chromaticities
+    channels: [(f64, Perez); 3],
+}
+
+impl Sky {
+    /// Sun `elevation` above the horizon and `azimuth` from -z towards +x in radians,
+    /// `turbidity` from 1.7 (very clear) to 10 (hazy)
+    pub fn new(elevation: f64, azimuth: f64, turbidity: f64, intensity: f64) -> Self {
+        let elevation = elevation.clamp(0.0, PI / 2.0);
+        let sun = Vector3::new(
+            elevation.cos() * azimuth.sin(),
+            elevation.sin(),
+            -elevation.cos() * azimuth.cos(),
+        );
+        let theta = PI / 2.0 - elevation;
+        let t = turbidity;
+
+        let chi = (4.0 / 9.0 - t / 120.0) * (PI - 2.0 * theta);
+        let zenith_luminance = (4.0453 * t - 4.9710) * chi.tan() - 0.2155 * t + 2.4192;
+        let chromaticity = |c: [[f64; 4]; 3]| {
+            let theta = [theta.powi(3), theta.powi(2), theta, 1.0];
+            let row = |r: [f64; 4]| (0..4).map(|i| r[i] * theta[i]).sum::<f64>();
+            t * t * row(c[0]) + t * row(c[1]) + row(c[2])
+        };
+        let zenith_x = chromaticity([
+            [0.00166, -0.00375, 0.00209, 0.0],
+            [-0.02903, 0.06377, -0.03202, 0.00394],
+            [0.11693, -0.21196, 0.06052, 0.25886],
+        ]);
+        let zenith_y = chromaticity([
+            [0.00275, -0.00610, 0.00317, 0.0],
+            [-0.04214, 0.08970, -0.04153, 0.00516],
+            [0.15346, -0.26756, 0.06670, 0.26688],
+        ]);
+
+        let model = Model {
+            sun,
+            intensity,
+            channels: [
+                (
+                    1.0,
+                    [
+                        0.1787 * t - 1.4630,
+                        -0.3554 * t + 0.4275,
+                        -0.0227 * t + 5.3251,
+                        0.1206 * t - 2.5771,
+                        -0.0670 * t + 0.3703,
+                    ],
+                ),
+                (
+                    zenith_x,
+                    [
+                        -0.0193 * t - 0.2592,
+                        -0.0665 * t + 0.0008,
+                        -0.0004 * t + 0.2125,
+                        -0.0641 * t - 0.8989,
+                        -0.0033 * t + 0.0452,
+                    ],
+                ),
+                (
+                    zenith_y,
+                    [
+                        -0.0167 * t - 0.2608,
+                        -0.0950 * t + 0.0092,
+                        -0.0079 * t + 0.2102,
+                        -0.0441 * t - 1.6537,
+                        -0.0109 * t + 0.0529,
+                    ],
+                ),
+            ],
+        };
+
+        let (width, height) = (128, 64);
+        let image = Rgb32FImage::from_fn(width, height, |column, row| {
+            let phi = ((column as f64 + 0.5) / width as f64 - 0.5) * 2.0 * PI;
+            let theta = (row as f64 + 0.5) / height as f64 * PI;
+            let direction = Vector3::new(
+                theta.sin() * phi.sin(),
+                theta.cos(),
+                -theta.sin() * phi.cos(),
+            );
+            let value = model.radiance(&direction);
+            image::Rgb([value.x as f32, value.y as f32, value.z as f32])
+        });
+
+        Self {
+            model,
+            turbidity,
+            zenith_luminance,
+            table: EnvironmentMap::from_image(image, 0.0, 1.0),
+        }
+    }
+
+    /// Sunlight through the atmosphere, `scale`d along with the sky. Rayleigh and
+    /// aerosol extinction at one wavelength per channel tint the sun towards red
+    /// as it sets.
+    pub fn sun_light(&self, scale: f64) -> DirectionalLight {
+        let sun = &self.model.sun;
+        let zenith_angle = sun.y.clamp(0.0, 1.0).acos().to_degrees();
+        // Kasten and Young
+        let air_mass = 1.0 / (sun.y.max(0.0) + 0.50572 * (96.07995 - zenith_angle).powf(-1.6364));
+        let aerosol = 0.04608 * self.turbidity - 0.04586;
+        let transmittance = |micrometers: f64| {
+            let depth = 0.008735 * micrometers.powf(-4.08) + aerosol * micrometers.powf(-1.3);
+            (-air_mass * depth).exp()
+        };
+        let color = spectrum::blackbody(5778.0)
+            * Vector3::new(
+                transmittance(0.68),
+                transmittance(0.55),
+                transmittance(0.44),
+            );
+
+        let irradiance = SOLAR_ILLUMINANCE / self.zenith_luminance * self.model.intensity * scale;
+        DirectionalLight::new(-sun, color * irradiance)
+    }
+}
+
+impl Model {
+    fn perez(coefficients: &Perez, cos_theta: f64, gamma: f64) -> f64 {
+        let [a, b, c, d, e] = coefficients;
+        (1.0 + a * (b / cos_theta).exp()) * (1.0 + c * (d * gamma).exp() + e * gamma.cos().powi(2))
+    }
+
+    fn radiance(&self, direction: &Vector3) -> Vector3 {
+        let direction = direction.normal();
+        // the model ends at the horizon, below it the horizon color carries on
+        let cos_theta = direction.y.max(1e-3);
+        let gamma = direction.dot(&self.sun).clamp(-1.0, 1.0).acos();
+        let sun_theta = self.sun.y.clamp(0.0, 1.0).acos();
+
+        let [luminance, x, y] = self.channels.map(|(zenith, coefficients)| {
+            zenith * Self::perez(&coefficients, cos_theta, gamma)
+                / Self::perez(&coefficients, 1.0, sun_theta)
+        });
+        let xyz = Vector3::new(x / y * luminance, luminance, (1.0 - x - y) / y * luminance);
+        let rgb = spectrum::xyz_to_linear_srgb(&xyz) * self.intensity;
+        Vector3::new(rgb.x.max(0.0), rgb.y.max(0.0), rgb.z.max(0.0))
+    }
+}
+
+impl Background for Sky {
+    fn value(&self, direction: &Vector3) -> Vector3 {
+        self.model.radiance(direction)
+    }
+
+    fn sample(&self) -> Option<BackgroundSample> {
+        let mut sample = self.table.sample()?;
+        sample.radiance = self.value(&sample.direction);
+        Some(sample)
+    }
+
+    fn pdf(&self, direction: &Vector3) -> f64 {
+        self.table.pdf(direction)
+    }
+}
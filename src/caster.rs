@@ -8,16 +8,17 @@ use futures::{
 use indicatif::{ProgressBar, ProgressStyle};
 
 use crate::{
-    component::{camera::Camera, scene::Scene},
+    component::scene::Scene,
     math::Vector3,
-    settings::Settings,
+    settings::{Integrator, Settings},
 };
 
 pub(crate) struct Caster {
     scene: Scene,
-    camera: Camera,
     pixel_samples: u32,
     max_depth: u32,
+    spectral: bool,
+    integrator: Integrator,
     output_file: String,
 }
 
@@ -29,44 +30,46 @@ impl Caster {
             scene,
         } = Settings::new()?;
 
-        let camera = Camera::build(camera);
-
-        let scene = Scene::build(scene);
+        let scene = Scene::build(scene, camera, &image)?;
 
         Ok(Self {
             scene,
-            camera,
             pixel_samples: image.pixel_samples,
             max_depth: image.max_depth,
+            spectral: image.spectral,
+            integrator: image.integrator,
             output_file: image.output,
         })
     }
 
     pub(crate) fn run(self) {
-        let buffer = self.camera.get_buffer();
+        if let Integrator::Sppm = self.integrator {
+            return self.run_passes();
+        }
+
+        let buffer = self.scene.camera.get_buffer();
         let buffer_size = buffer.len();
 
-        let bar = ProgressBar::new(buffer_size as u64).with_style(
-            ProgressStyle::default_bar()
-                .template("{wide_bar} {percent_precise:>7}%/100%\n{wide_msg} {elapsed_precise:>}")
-                .unwrap(),
-        );
+        let bar = progress_bar(buffer_size as u64);
 
         let buffer = bar
             .wrap_stream(stream::iter(buffer))
             .map(|buf_idx| {
                 stream::iter(vec![buf_idx; self.pixel_samples as usize])
                     .enumerate()
-                    .map(|(samp_idx, buf_idx)| (self.camera.sample_ray(buf_idx), buf_idx, samp_idx))
+                    .map(|(samp_idx, buf_idx)| {
+                        (self.scene.camera.sample_ray(buf_idx), buf_idx, samp_idx)
+                    })
                     .map(|(ray, buf_idx, samp_idx)| {
-                        self.scene.cast(ray, buf_idx, samp_idx, self.max_depth)
+                        self.scene
+                            .cast(ray, buf_idx, samp_idx, self.max_depth, self.spectral)
                     })
                     .buffer_unordered(self.pixel_samples as usize)
                     .fold(Vector3::fill(0.0), |acc, v| async move { acc + v })
-                    .map(move |v| (buf_idx, (v / self.pixel_samples).get_color()))
+                    .map(move |v| (buf_idx, v / self.pixel_samples))
             })
-            .buffer_unordered(self.camera.image_width as usize)
-            .collect::<Vec<(u32, Vec<u8>)>>();
+            .buffer_unordered(self.scene.camera.image_width as usize)
+            .collect::<Vec<(u32, Vector3)>>();
 
         println!(
             "Start time - {} | # of pixels - {buffer_size} | Worker threads - {} | Output file - {}",
@@ -78,9 +81,52 @@ impl Caster {
         let mut buffer = executor::block_on(buffer);
         bar.finish();
 
-        buffer.sort_by(|(a, _), (b, _)| a.cmp(b));
-        let buffer = buffer.into_iter().flat_map(|v| v.1).collect::<Vec<u8>>();
+        buffer.sort_by_key(|(idx, _)| *idx);
+        self.save(buffer.into_iter().map(|(_, v)| v).collect());
+    }
+
+    /// Photon mapping goes through every pixel once per pass
+    fn run_passes(self) {
+        let bar = progress_bar(self.pixel_samples as u64);
+
+        println!(
+            "Start time - {} | # of pixels - {} | Worker threads - {} | Output file - {}",
+            Local::now().format("%H:%M:%S"),
+            self.scene.camera.pixel_count(),
+            self.scene.thread_count,
+            self.output_file,
+        );
+
+        let pixels = self
+            .scene
+            .render_photons(self.pixel_samples, self.max_depth, self.spectral, || {
+                bar.inc(1)
+            })
+            .unwrap_or_default();
+        bar.finish();
+
+        self.save(pixels);
+    }
+
+    /// Writes the linear colors of every pixel in order
+    fn save(&self, pixels: Vec<Vector3>) {
+        // light traced to the lens lands on any pixel, so it is only added at the end
+        let buffer = pixels
+            .into_iter()
+            .zip(0..)
+            .flat_map(|(v, idx)| {
+                (v + self.scene.camera.splat_value(idx) / self.pixel_samples).get_color()
+            })
+            .collect::<Vec<u8>>();
 
-        self.camera.render(buffer, &self.output_file);
+        self.scene.camera.render(buffer, &self.output_file);
     }
 }
+
+fn progress_bar(length: u64) -> ProgressBar {
+    ProgressBar::new(length).with_style(
+        ProgressStyle::default_bar()
+            .template("{wide_bar} {percent_precise:>7}%/100%\n{wide_msg} {elapsed_precise:>}")
+            .unwrap(),
+    )
+}
@@ -0,0 +1,565 @@
+use std::{f64::consts::PI, sync::Arc};
+
+use crate::{
+    geometry::T_EPSILON,
+    material::MaterialRef,
+    math::Vector3,
+    spectrum::{self, upsample_traced},
+};
+
+use super::{
+    camera::Camera,
+    hit::HitRecord,
+    ray::{self, Ray},
+    sources::{Source, Sources},
+    world::World,
+};
+
+#[derive(Clone)]
+enum Kind {
+    Camera,
+    /// Start of a light subpath, the index of its source
+    Light(usize),
+    /// Index of the object hit
+    Surface(usize),
+}
+
+#[derive(Clone)]
+struct Vertex {
+    kind: Kind,
+    point: Vector3,
+    /// Surfaces and emitters, normals facing the side the path arrived from
+    hit: Option<(HitRecord, MaterialRef)>,
+    /// Throughput of the subpath up to the vertex
+    beta: Vector3,
+    /// Densities over area of sampling the vertex from the start of its subpath, and
+    /// from the other end of the path
+    pdf_fwd: f64,
+    pdf_rev: f64,
+    /// Scatters through lobes sampled lights can't reach, so paths can't be connected here
+    delta: bool,
+    /// Only the hero wavelength is carried
+    dispersed: bool,
+}
+
+impl Vertex {
+    fn new(kind: Kind, point: Vector3, beta: Vector3, pdf_fwd: f64) -> Self {
+        Self {
+            kind,
+            point,
+            hit: None,
+            beta,
+            pdf_fwd,
+            pdf_rev: 0.0,
+            delta: false,
+            dispersed: false,
+        }
+    }
+
+    /// Geometric normal of vertices on a surface, volumes and points have none
+    fn normal(&self) -> Option<&Vector3> {
+        match &self.hit {
+            Some((record, material)) if !material.volumetric() => Some(&record.normal),
+            _ => None,
+        }
+    }
+
+    /// BSDF times the cosine towards `point`, for light arriving from it
+    fn eval(&self, point: &Vector3, wavelengths: Option<&[f64; 3]>) -> Option<Vector3> {
+        let (record, material) = self.hit.as_ref()?;
+        let value = material.eval(record, &(point - &self.point).normal())?;
+        Some(upsample_traced(value, wavelengths))
+    }
+}
+
+/// Bidirectional path tracer, connects every vertex of a camera subpath with every vertex
+/// of a light subpath and weights the connections by multiple importance sampling.
+/// Distant lights and the background are only reached from the camera.
+pub(crate) struct Bidirectional {
+    camera: Arc<Camera>,
+    sources: Sources,
+}
+
+impl Bidirectional {
+    pub(crate) fn new(world: &World, camera: Arc<Camera>) -> Self {
+        Self {
+            camera,
+            sources: Sources::new(world),
+        }
+    }
+
+    /// Light arriving along the camera ray, paths leaving through lenses splat on the
+    /// camera instead
+    pub(crate) fn radiance(
+        &self,
+        ray: &Ray,
+        max_depth: u32,
+        wavelengths: Option<&[f64; 3]>,
+        world: &World,
+    ) -> Vector3 {
+        let max_depth = max_depth as usize;
+
+        let direction = ray.direction.normal();
+        let pdf = self.camera.pdf_direction(&ray.origin, &direction);
+        let mut camera_path = vec![Vertex::new(
+            Kind::Camera,
+            ray.origin.clone(),
+            Vector3::fill(1.0),
+            1.0,
+        )];
+        let ray = Ray::new(ray.origin.clone(), direction);
+        let mut radiance = self.walk(
+            world,
+            ray,
+            Vector3::fill(1.0),
+            pdf,
+            max_depth + 2,
+            wavelengths,
+            true,
+            &mut camera_path,
+        );
+        let light_path = self.light_subpath(world, max_depth + 1, wavelengths);
+
+        for t in 1..=camera_path.len() {
+            for s in 0..=light_path.len() {
+                if s + t < 2 || (s == 1 && t == 1) || s + t - 2 > max_depth {
+                    continue;
+                }
+                if t == 1 {
+                    self.splat(world, &light_path, s, wavelengths);
+                } else {
+                    radiance += self.connect(world, &camera_path, &light_path, s, t, wavelengths);
+                }
+            }
+        }
+        radiance
+    }
+
+    /// Extends the path from its last vertex until it leaves the scene, is absorbed or
+    /// holds `max_vertices`. Camera paths return the light of the background and the
+    /// distant lights on the way.
+    #[allow(clippy::too_many_arguments)]
+    fn walk(
+        &self,
+        world: &World,
+        mut ray: Ray,
+        mut beta: Vector3,
+        mut pdf: f64,
+        max_vertices: usize,
+        wavelengths: Option<&[f64; 3]>,
+        from_camera: bool,
+        path: &mut Vec<Vertex>,
+    ) -> Vector3 {
+        let mut radiance = Vector3::fill(0.0);
+        let mut dispersed = false;
+        let mut scatter_pdf = None;
+
+        while path.len() < max_vertices {
+            let Some((object, hit)) = world.hit_object(&ray) else {
+                if from_camera {
+                    let direction = &ray.direction;
+                    let weight = match scatter_pdf {
+                        Some(pdf) => ray::power_heuristic(pdf, world.background.pdf(direction)),
+                        None => 1.0,
+                    };
+                    let value = world.background.value(direction) * weight;
+                    radiance += &beta * upsample_traced(value, wavelengths);
+                }
+                break;
+            };
+            let material = hit.material;
+            let mut record = hit.record;
+            record.wavelengths = wavelengths.copied();
+
+            let delta = material.is_delta(&record);
+            if from_camera && !delta && path.len() + 2 <= max_vertices {
+                let value = ray::distant_light(&record, &material, world)
+                    + ray::environment_light(&record, &material, world);
+                radiance += &beta * upsample_traced(value, wavelengths);
+            }
+
+            let mut vertex = Vertex::new(
+                Kind::Surface(object),
+                record.point.clone(),
+                beta.clone(),
+                0.0,
+            );
+            vertex.hit = Some((record.clone(), material.clone()));
+            vertex.delta = delta;
+            vertex.dispersed = dispersed;
+            if let Some(previous) = path.last() {
+                vertex.pdf_fwd = convert_density(pdf, previous, &vertex);
+            }
+            path.push(vertex);
+            if path.len() >= max_vertices {
+                break;
+            }
+
+            let sampled_record = record.clone();
+            let Some(scattered) = material.scatter(record) else {
+                break;
+            };
+            let direction = scattered.ray.direction.normal();
+            scatter_pdf = material.pdf(&sampled_record, &direction);
+            let pdf_rev = if delta {
+                pdf = 0.0;
+                0.0
+            } else {
+                pdf = scatter_pdf.unwrap_or(0.0);
+                let reversed = sampled_record.arriving(&-&direction);
+                material.pdf(&reversed, &-&ray.direction).unwrap_or(0.0)
+            };
+
+            let mut value = upsample_traced(scattered.attenuation, wavelengths);
+            if scattered.dispersed && !dispersed && wavelengths.is_some() {
+                dispersed = true;
+                value *= Vector3::new(3.0, 0.0, 0.0);
+            }
+            beta *= value;
+
+            let count = path.len();
+            if count >= 2 {
+                path[count - 2].pdf_rev =
+                    convert_density(pdf_rev, &path[count - 1], &path[count - 2]);
+            }
+            ray = Ray::new(scattered.ray.origin, direction);
+        }
+        radiance
+    }
+
+    /// Path starting on a source picked by power, empty without sources
+    fn light_subpath(
+        &self,
+        world: &World,
+        max_vertices: usize,
+        wavelengths: Option<&[f64; 3]>,
+    ) -> Vec<Vertex> {
+        let Some(emission) = self.sources.emit(world, wavelengths) else {
+            return Vec::new();
+        };
+        let beta = emission.power();
+        let mut start = Vertex::new(
+            Kind::Light(emission.source),
+            emission.ray.origin.clone(),
+            &emission.emitted / emission.pdf_position,
+            emission.pdf_position,
+        );
+        start.hit = emission.hit;
+        let (ray, pdf) = (emission.ray, emission.pdf_direction);
+
+        let mut path = vec![start];
+        self.walk(
+            world,
+            ray,
+            beta,
+            pdf,
+            max_vertices,
+            wavelengths,
+            false,
+            &mut path,
+        );
+        path
+    }
+
+    /// Light of the strategy with `s` light and `t` camera vertices, `t` at least 2
+    fn connect(
+        &self,
+        world: &World,
+        camera_path: &[Vertex],
+        light_path: &[Vertex],
+        s: usize,
+        t: usize,
+        wavelengths: Option<&[f64; 3]>,
+    ) -> Vector3 {
+        let zero = Vector3::fill(0.0);
+        let pt = &camera_path[t - 1];
+
+        // the camera path found a light by itself
+        if s == 0 {
+            let (Some((record, material)), Kind::Surface(object)) = (&pt.hit, &pt.kind) else {
+                return zero;
+            };
+            let emitted = upsample_traced(material.emit(record), wavelengths);
+            if emitted.near_zero() {
+                return zero;
+            }
+            let weight = match world.emitter_of(*object) {
+                Some(_) => self.mis_weight(world, camera_path, light_path, s, t, None),
+                // no other strategy finds it
+                None => 1.0,
+            };
+            return &pt.beta * emitted * weight;
+        }
+        if pt.delta {
+            return zero;
+        }
+
+        // a new point on a source
+        if s == 1 {
+            let Some(sampled) = self.sample_source(world, pt, wavelengths) else {
+                return zero;
+            };
+            let Some(value) = pt.eval(&sampled.point, wavelengths) else {
+                return zero;
+            };
+            let transmittance = transmittance(world, &pt.point, &sampled.point);
+            if transmittance <= 0.0 {
+                return zero;
+            }
+            let value = &pt.beta * value * &sampled.beta * transmittance;
+            let weight = self.mis_weight(world, camera_path, light_path, s, t, Some(&sampled));
+            return value * weight;
+        }
+
+        let qs = &light_path[s - 1];
+        if qs.delta {
+            return zero;
+        }
+        let (Some(camera_side), Some(light_side)) = (
+            pt.eval(&qs.point, wavelengths),
+            qs.eval(&pt.point, wavelengths),
+        ) else {
+            return zero;
+        };
+        let transmittance = transmittance(world, &pt.point, &qs.point);
+        if transmittance <= 0.0 {
+            return zero;
+        }
+        let mut value = &pt.beta * camera_side * light_side * &qs.beta * transmittance
+            / (&qs.point - &pt.point).quadrance();
+        if pt.dispersed && qs.dispersed {
+            // both picked the same hero wavelength
+            value = value / 3.0;
+        }
+        value * self.mis_weight(world, camera_path, light_path, s, t, None)
+    }
+
+    /// Light path vertex seen straight from the lens, added to the pixel it lands in
+    fn splat(
+        &self,
+        world: &World,
+        light_path: &[Vertex],
+        s: usize,
+        wavelengths: Option<&[f64; 3]>,
+    ) {
+        let qs = &light_path[s - 1];
+        if qs.delta {
+            return;
+        }
+        let Some(connection) = self.camera.connect(&qs.point) else {
+            return;
+        };
+        let Some(value) = qs.eval(&connection.origin, wavelengths) else {
+            return;
+        };
+        let transmittance = transmittance(world, &qs.point, &connection.origin);
+        if transmittance <= 0.0 {
+            return;
+        }
+
+        let sampled = Vertex::new(Kind::Camera, connection.origin, Vector3::fill(1.0), 1.0);
+        let weight = self.mis_weight(world, &[], light_path, s, 1, Some(&sampled));
+        let value = &qs.beta * value * (connection.importance * weight * transmittance);
+        let value = match wavelengths {
+            Some(wavelengths) => spectrum::to_rgb(&value, wavelengths),
+            None => value,
+        };
+        self.camera.splat(connection.pixel, value);
+    }
+
+    /// Point on a source picked by power, lighting `pt`. Its throughput already holds
+    /// the light arriving at `pt` over the squared distance and the cosine at the light.
+    fn sample_source(
+        &self,
+        world: &World,
+        pt: &Vertex,
+        wavelengths: Option<&[f64; 3]>,
+    ) -> Option<Vertex> {
+        let (source, probability) = self.sources.pick()?;
+        match self.sources.sources[source] {
+            Source::Emitter(object) => {
+                let object = &world.objects[object];
+                let hit = object.sample_emitting()?;
+                let offset = &hit.record.point - &pt.point;
+                let distance_squared = offset.quadrance();
+                let direction = offset.normal();
+                let mut record = hit.record.arriving(&direction);
+                record.wavelengths = wavelengths.copied();
+
+                let emitted = upsample_traced(hit.material.emit(&record), wavelengths);
+                let cos_theta = record.normal.dot(&direction).abs();
+                let pdf_position = probability / object.emitting_area();
+                let mut sampled = Vertex::new(
+                    Kind::Light(source),
+                    record.point.clone(),
+                    emitted * (cos_theta / (pdf_position * distance_squared)),
+                    pdf_position,
+                );
+                sampled.hit = Some((record, hit.material));
+                Some(sampled)
+            }
+            Source::Light(index) => {
+                let sample = world.lights[index].sample(&pt.point)?;
+                let irradiance = upsample_traced(sample.irradiance, wavelengths);
+                Some(Vertex::new(
+                    Kind::Light(source),
+                    &pt.point + &sample.direction * sample.distance,
+                    irradiance / probability,
+                    probability,
+                ))
+            }
+        }
+    }
+
+    /// Balance heuristic over every strategy that could have sampled the same path,
+    /// `sampled` replaces the end vertex of a subpath with a single vertex
+    fn mis_weight(
+        &self,
+        world: &World,
+        camera_path: &[Vertex],
+        light_path: &[Vertex],
+        s: usize,
+        t: usize,
+        sampled: Option<&Vertex>,
+    ) -> f64 {
+        if s + t == 2 {
+            return 1.0;
+        }
+
+        let pt = match t {
+            1 => sampled,
+            _ => camera_path.get(t - 1),
+        };
+        let qs = match s {
+            0 => None,
+            1 => sampled,
+            _ => light_path.get(s - 1),
+        };
+        let Some(pt) = pt else {
+            return 0.0;
+        };
+        let pt_minus = t.checked_sub(2).and_then(|i| camera_path.get(i));
+        let qs_minus = s.checked_sub(2).and_then(|i| light_path.get(i));
+
+        // densities of both subpaths, with the connection taken into account
+        let mut camera: Vec<(f64, f64, bool)> = camera_path
+            .iter()
+            .take(t)
+            .map(|v| (v.pdf_fwd, v.pdf_rev, v.delta))
+            .collect();
+        camera.resize(t, (1.0, 0.0, false));
+        let mut light: Vec<(f64, f64, bool)> = light_path
+            .iter()
+            .take(s)
+            .map(|v| (v.pdf_fwd, v.pdf_rev, v.delta))
+            .collect();
+        if let (1, Some(qs)) = (s, qs) {
+            light = vec![(qs.pdf_fwd, 0.0, false)];
+        }
+
+        camera[t - 1].1 = match qs {
+            Some(qs) => self.pdf(world, qs, qs_minus, pt),
+            None => self.pdf_light_origin(world, pt),
+        };
+        camera[t - 1].2 = false;
+        if let Some(pt_minus) = pt_minus {
+            camera[t - 2].1 = match qs {
+                Some(qs) => self.pdf(world, pt, Some(qs), pt_minus),
+                None => self.pdf_light(world, pt, pt_minus),
+            };
+        }
+        if let Some(qs) = qs {
+            light[s - 1].1 = self.pdf(world, pt, pt_minus, qs);
+            light[s - 1].2 = false;
+            if let Some(qs_minus) = qs_minus {
+                light[s - 2].1 = self.pdf(world, qs, Some(pt), qs_minus);
+            }
+        }
+        // lights at a single point can't be hit
+        let start = if s == 1 { qs } else { light_path.first() };
+        let delta_light = start.is_some_and(|v| v.hit.is_none());
+
+        let remap = |pdf: f64| if pdf != 0.0 { pdf } else { 1.0 };
+        let mut sum = 0.0;
+        let mut ratio = 1.0;
+        for i in (1..t).rev() {
+            ratio *= remap(camera[i].1) / remap(camera[i].0);
+            if !camera[i].2 && !camera[i - 1].2 {
+                sum += ratio;
+            }
+        }
+        ratio = 1.0;
+        for i in (0..s).rev() {
+            ratio *= remap(light[i].1) / remap(light[i].0);
+            let delta_before = if i > 0 { light[i - 1].2 } else { delta_light };
+            if !light[i].2 && !delta_before {
+                sum += ratio;
+            }
+        }
+        1.0 / (1.0 + sum)
+    }
+
+    /// Density over area of `vertex`, reached from `previous`, sampling `next`
+    fn pdf(&self, world: &World, vertex: &Vertex, previous: Option<&Vertex>, next: &Vertex) -> f64 {
+        let direction = (&next.point - &vertex.point).normal();
+        let pdf = match &vertex.kind {
+            Kind::Camera => self.camera.pdf_direction(&vertex.point, &direction),
+            Kind::Light(_) => return self.pdf_light(world, vertex, next),
+            Kind::Surface(_) => {
+                let (Some(previous), Some((record, material))) = (previous, &vertex.hit) else {
+                    return 0.0;
+                };
+                let record = record.arriving(&(&vertex.point - &previous.point).normal());
+                material.pdf(&record, &direction).unwrap_or(0.0)
+            }
+        };
+        convert_density(pdf, vertex, next)
+    }
+
+    /// Density over area of a light subpath starting at `vertex` reaching `next`
+    fn pdf_light(&self, world: &World, vertex: &Vertex, next: &Vertex) -> f64 {
+        let direction = (&next.point - &vertex.point).normal();
+        let pdf = match (&vertex.hit, &vertex.kind) {
+            (Some((record, _)), _) => record.normal.dot(&direction).abs() / (2.0 * PI),
+            (None, Kind::Light(source)) => match self.sources.sources[*source] {
+                Source::Light(index) => world.lights[index].pdf_emission(&direction),
+                Source::Emitter(_) => 0.0,
+            },
+            _ => 0.0,
+        };
+        convert_density(pdf, vertex, next)
+    }
+
+    /// Density over area of a light subpath starting at the emitter the camera path hit
+    fn pdf_light_origin(&self, world: &World, vertex: &Vertex) -> f64 {
+        let Kind::Surface(object) = vertex.kind else {
+            return 0.0;
+        };
+        let Some(source) = world.emitter_of(object) else {
+            return 0.0;
+        };
+        self.sources.probability(source) / world.objects[object].emitting_area()
+    }
+}
+
+/// Density over solid angle at `from` turned into density over area at `to`
+fn convert_density(pdf: f64, from: &Vertex, to: &Vertex) -> f64 {
+    let offset = &to.point - &from.point;
+    let distance_squared = offset.quadrance();
+    if distance_squared == 0.0 {
+        return 0.0;
+    }
+    let pdf = pdf / distance_squared;
+    match to.normal() {
+        Some(normal) => pdf * normal.dot(&offset).abs() / distance_squared.sqrt(),
+        None => pdf,
+    }
+}
+
+/// Share of the light getting from one point to the other
+fn transmittance(world: &World, from: &Vector3, to: &Vector3) -> f64 {
+    let offset = to - from;
+    let distance = offset.len();
+    let ray = Ray::new(from.clone(), offset / distance);
+    world.transmittance(&ray, distance - T_EPSILON)
+}
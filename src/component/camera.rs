@@ -1,3 +1,5 @@
+use std::sync::Mutex;
+
 use image::RgbImage;
 use rand::Rng;
 
@@ -12,12 +14,26 @@ pub struct Camera {
     pixel_delta_v: Vector3,
     // u: Vector3,
     // v: Vector3,
-    // w: Vector3,
+    /// Unit vector pointing backwards, away from the scene
+    w: Vector3,
     defocus_disk_u: Vector3,
     defocus_disk_v: Vector3,
     defocus_angle: f64,
+    focus_distance: f64,
+    /// Area of the viewport moved to a unit distance from the lens
+    film_area: f64,
     pub image_width: u32,
     image_height: u32,
+    /// Light traced straight to the lens, summed per pixel over every sample
+    splats: Vec<Mutex<Vector3>>,
+}
+
+/// Lens point seeing a point of the scene
+pub struct CameraConnection {
+    pub origin: Vector3,
+    pub pixel: u32,
+    /// Importance arriving at the point, already divided by the squared distance
+    pub importance: f64,
 }
 
 impl Camera {
@@ -59,7 +75,7 @@ impl Camera {
 
         // Upper left pixel
         let viewport_upper_left =
-            &look_from - w * focus_distance - viewport_u / 2.0 - viewport_v / 2.0;
+            &look_from - &w * focus_distance - viewport_u / 2.0 - viewport_v / 2.0;
         let pixel_upper_left = viewport_upper_left + (&pixel_delta_u + &pixel_delta_v) / 2.0;
 
         // Camera defocus disk
@@ -74,15 +90,27 @@ impl Camera {
             pixel_delta_v,
             // u,
             // v,
-            // w,
+            w,
             defocus_disk_u,
             defocus_disk_v,
             defocus_angle,
+            focus_distance,
+            film_area: viewport_width * viewport_height / (focus_distance * focus_distance),
             image_width,
             image_height,
+            splats: Vec::new(),
         }
     }
 
+    /// Lets light paths add to the pixels
+    pub fn with_splats(mut self) -> Self {
+        let buf_size = (self.image_width * self.image_height) as usize;
+        self.splats = (0..buf_size)
+            .map(|_| Mutex::new(Vector3::fill(0.0)))
+            .collect();
+        self
+    }
+
     fn pixel_sample_rand(&self) -> Vector3 {
         let mut rng = rand::rng();
         let px = -0.5 + rng.random::<f64>();
@@ -114,6 +142,78 @@ impl Camera {
         Ray::new(ray_origin, ray_direction)
     }
 
+    pub fn pixel_count(&self) -> u32 {
+        self.image_width * self.image_height
+    }
+
+    pub fn focus_distance(&self) -> f64 {
+        self.focus_distance
+    }
+
+    /// Width of a pixel at the focus distance
+    pub fn pixel_size(&self) -> f64 {
+        self.pixel_delta_u.len()
+    }
+
+    /// Pixel the ray from a lens point crosses the focus plane in
+    fn pixel(&self, origin: &Vector3, direction: &Vector3, cos_theta: f64) -> Option<u32> {
+        let focus_point = origin + direction * (self.focus_distance / cos_theta);
+        let corner = &self.pixel_upper_left - (&self.pixel_delta_u + &self.pixel_delta_v) / 2.0;
+        let offset = focus_point - corner;
+        let x = offset.dot(&self.pixel_delta_u) / self.pixel_delta_u.quadrance();
+        let y = offset.dot(&self.pixel_delta_v) / self.pixel_delta_v.quadrance();
+        if x < 0.0 || y < 0.0 || x >= self.image_width as f64 || y >= self.image_height as f64 {
+            return None;
+        }
+        Some(y as u32 * self.image_width + x as u32)
+    }
+
+    /// Density of a ray from the lens point `origin` leaving in the unit `direction`,
+    /// over the sphere and with the pixel picked uniformly
+    pub fn pdf_direction(&self, origin: &Vector3, direction: &Vector3) -> f64 {
+        let cos_theta = -direction.dot(&self.w);
+        if cos_theta <= 0.0 || self.pixel(origin, direction, cos_theta).is_none() {
+            return 0.0;
+        }
+        1.0 / (self.film_area * cos_theta.powi(3))
+    }
+
+    /// Lens point and pixel `point` is seen from, `None` outside the image
+    pub fn connect(&self, point: &Vector3) -> Option<CameraConnection> {
+        let origin = if self.defocus_angle <= 0.0 {
+            self.look_from.clone()
+        } else {
+            self.defocus_disk_sample()
+        };
+        let offset = point - &origin;
+        let distance = offset.len();
+        let direction = offset / distance;
+        let cos_theta = -direction.dot(&self.w);
+        if cos_theta <= 0.0 {
+            return None;
+        }
+        let pixel = self.pixel(&origin, &direction, cos_theta)?;
+        Some(CameraConnection {
+            origin,
+            pixel,
+            importance: 1.0 / (self.film_area * cos_theta.powi(3) * distance * distance),
+        })
+    }
+
+    pub fn splat(&self, pixel: u32, value: Vector3) {
+        if let Some(Ok(mut splat)) = self.splats.get(pixel as usize).map(|s| s.lock()) {
+            *splat += value;
+        }
+    }
+
+    /// Sum of the light splatted on the pixel
+    pub fn splat_value(&self, pixel: u32) -> Vector3 {
+        match self.splats.get(pixel as usize).map(|s| s.lock()) {
+            Some(Ok(splat)) => splat.clone(),
+            _ => Vector3::fill(0.0),
+        }
+    }
+
     pub fn get_buffer(&self) -> Vec<u32> {
         let buf_size = self.image_width * self.image_height;
         let mid = buf_size / 2;
@@ -0,0 +1,128 @@
+use crate::{math::Vector3, settings::Integrator};
+
+use super::{hit::Hit, ray::Ray, sources::cosine_direction, world::World};
+
+/// Quick looks at the scene from the first hit of every camera ray, without tracing light
+#[derive(Clone, Copy)]
+pub(crate) enum View {
+    /// Radius to look for blockers within
+    AmbientOcclusion(f64),
+    Normals,
+    Albedo,
+    /// Distance shown black and beyond
+    Depth(f64),
+    Uv,
+    Barycentrics,
+    MaterialId,
+    RayDepth,
+}
+
+impl View {
+    /// View of the debug integrators, `None` for the ones tracing light
+    pub(crate) fn new(integrator: Integrator, ao_radius: f64, focus_distance: f64) -> Option<Self> {
+        match integrator {
+            Integrator::Path | Integrator::Bdpt | Integrator::Sppm => None,
+            Integrator::AmbientOcclusion => Some(Self::AmbientOcclusion(ao_radius)),
+            Integrator::Normals => Some(Self::Normals),
+            Integrator::Albedo => Some(Self::Albedo),
+            Integrator::Depth => Some(Self::Depth(2.0 * focus_distance)),
+            Integrator::Uv => Some(Self::Uv),
+            Integrator::Barycentrics => Some(Self::Barycentrics),
+            Integrator::MaterialId => Some(Self::MaterialId),
+            Integrator::RayDepth => Some(Self::RayDepth),
+        }
+    }
+
+    /// Color of the camera ray
+    pub(crate) fn value(&self, ray: &Ray, max_depth: u32, world: &World) -> Vector3 {
+        match self {
+            Self::AmbientOcclusion(radius) => match world.hit(ray) {
+                Some(hit) => {
+                    let direction = cosine_direction(&hit.record.shading_normal);
+                    let ray = Ray::new(hit.record.point, direction);
+                    Vector3::fill(if world.occluded(&ray, *radius) {
+                        0.0
+                    } else {
+                        1.0
+                    })
+                }
+                None => Vector3::fill(1.0),
+            },
+            Self::Normals => on_hit(ray, world, |hit| {
+                let record = hit.record;
+                let normal = if record.front {
+                    record.shading_normal
+                } else {
+                    -record.shading_normal
+                };
+                raw((normal + Vector3::fill(1.0)) / 2.0)
+            }),
+            Self::Albedo => on_hit(ray, world, |hit| {
+                hit.material
+                    .scatter(hit.record)
+                    .map_or(Vector3::fill(0.0), |scattered| scattered.attenuation)
+            }),
+            Self::Depth(far) => on_hit(ray, world, |hit| {
+                let distance = hit.record.t * ray.direction.len();
+                raw(Vector3::fill((1.0 - distance / far).max(0.0)))
+            }),
+            Self::Uv => on_hit(ray, world, |hit| {
+                raw(Vector3::new(hit.record.u, hit.record.v, 0.0))
+            }),
+            Self::Barycentrics => on_hit(ray, world, |hit| {
+                raw(hit.record.barycentrics.unwrap_or(Vector3::fill(0.0)))
+            }),
+            Self::MaterialId => on_hit(ray, world, |hit| {
+                // Fibonacci hashing spreads neighbouring indices over the colors
+                let id = world
+                    .material_id(&hit.material)
+                    .map_or(0, |id| id as u64 + 1);
+                let hash = id.wrapping_mul(0x9e37_79b9_7f4a_7c15);
+                raw(Vector3::new(
+                    (hash >> 56) as f64 / 255.0,
+                    (hash >> 48 & 0xff) as f64 / 255.0,
+                    (hash >> 40 & 0xff) as f64 / 255.0,
+                ))
+            }),
+            Self::RayDepth => ray_depth(ray, max_depth, world),
+        }
+    }
+}
+
+/// Color of the closest hit, black when the ray misses everything
+fn on_hit(ray: &Ray, world: &World, color: impl FnOnce(Hit) -> Vector3) -> Vector3 {
+    world.hit(ray).map_or(Vector3::fill(0.0), color)
+}
+
+/// Heat of the bounces a path takes by scattering alone
+fn ray_depth(ray: &Ray, max_depth: u32, world: &World) -> Vector3 {
+    let mut ray = Ray::new(ray.origin.clone(), ray.direction.clone());
+    let mut bounces = 0;
+    while bounces < max_depth {
+        let Some(scattered) = world
+            .hit(&ray)
+            .and_then(|hit| hit.material.scatter(hit.record))
+        else {
+            break;
+        };
+        ray = scattered.ray;
+        bounces += 1;
+    }
+
+    let heat = bounces as f64 / max_depth.max(1) as f64;
+    let (blue, green, red) = (
+        Vector3::new(0.0, 0.0, 1.0),
+        Vector3::new(0.0, 1.0, 0.0),
+        Vector3::new(1.0, 0.0, 0.0),
+    );
+    raw(if heat < 0.5 {
+        blue * (1.0 - 2.0 * heat) + green * (2.0 * heat)
+    } else {
+        green * (2.0 - 2.0 * heat) + red * (2.0 * heat - 1.0)
+    })
+}
+
+/// Squared, so the gamma of the output writes the value itself
+fn raw(value: Vector3) -> Vector3 {
+    &value * &value
+}
@@ -1,16 +1,41 @@
-use std::sync::Arc;
-
-use crate::{material::Material, math::Vector3};
+use crate::{material::MaterialRef, math::Vector3};
 
 pub(crate) struct Hit {
     pub(crate) record: HitRecord,
-    pub(crate) material: Arc<dyn Material>,
+    pub(crate) material: MaterialRef,
 }
 
+#[derive(Clone)]
 pub(crate) struct HitRecord {
     pub(crate) point: Vector3,
+    /// Geometric normal, facing against the ray
     pub(crate) normal: Vector3,
+    /// Interpolated or perturbed normal used for shading, on the same side as `normal`
+    pub(crate) shading_normal: Vector3,
+    /// Direction of growing `u` on the surface, `None` where the surface has no parametrization
+    pub(crate) tangent: Option<Vector3>,
     pub(crate) direction: Vector3,
     pub(crate) t: f64,
     pub(crate) front: bool,
+    pub(crate) u: f64,
+    pub(crate) v: f64,
+    pub(crate) vertex_color: Option<Vector3>,
+    /// Weights of the corners of the triangle hit, `None` off meshes
+    pub(crate) barycentrics: Option<Vector3>,
+    /// Hero wavelength and its companions in nanometers when tracing spectrally
+    pub(crate) wavelengths: Option<[f64; 3]>,
+}
+
+impl HitRecord {
+    /// Same hit for a ray arriving along the unit `direction`, normals flipped to face it
+    pub(crate) fn arriving(&self, direction: &Vector3) -> Self {
+        let mut record = self.clone();
+        if direction.dot(&record.normal) > 0.0 {
+            record.normal = -&record.normal;
+            record.shading_normal = -&record.shading_normal;
+            record.front = !record.front;
+        }
+        record.direction = direction.clone();
+        record
+    }
 }
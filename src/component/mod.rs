@@ -1,4 +1,9 @@
+pub mod bidirectional;
 pub mod camera;
+pub mod debug;
 pub mod hit;
+pub mod photon_map;
 pub mod ray;
 pub mod scene;
+pub mod sources;
+pub mod world;
@@ -0,0 +1,452 @@
+use std::{f64::consts::PI, sync::Arc, thread};
+
+use crate::{
+    geometry::aabb::Aabb,
+    material::MaterialRef,
+    math::Vector3,
+    spectrum::{self, upsample_traced},
+};
+
+use super::{camera::Camera, hit::HitRecord, ray, sources::Sources, world::World};
+
+/// Share of the photons found in a pass that is kept, the rest shrinks the radius
+const ALPHA: f64 = 2.0 / 3.0;
+
+/// First diffuse surface hit of a camera ray in a pass
+struct VisiblePoint {
+    record: HitRecord,
+    material: MaterialRef,
+    /// Throughput of the camera ray up to the hit
+    beta: Vector3,
+    dispersed: bool,
+}
+
+/// Running estimate of a pixel over the passes
+#[derive(Clone)]
+struct PixelEstimate {
+    radius: f64,
+    /// Photons gathered so far, fewer than found as the radius shrinks
+    count: f64,
+    /// Flux gathered within the radius, scaled down with it
+    flux: Vector3,
+    /// Summed light the camera rays find by themselves: emitters, distant lights and the
+    /// background
+    direct: Vector3,
+}
+
+struct Photon {
+    point: Vector3,
+    /// Unit direction the photon travelled
+    direction: Vector3,
+    power: Vector3,
+    dispersed: bool,
+}
+
+/// Photons sorted in place into a balanced kd-tree, every node the median of its range
+/// along the widest axis
+struct KdTree {
+    photons: Vec<Photon>,
+    axes: Vec<u8>,
+}
+
+impl KdTree {
+    fn new(mut photons: Vec<Photon>) -> Self {
+        let mut axes = vec![0; photons.len()];
+        Self::build(&mut photons, &mut axes);
+        Self { photons, axes }
+    }
+
+    fn build(photons: &mut [Photon], axes: &mut [u8]) {
+        if photons.len() <= 1 {
+            return;
+        }
+        let mut bounds = Aabb::empty();
+        photons.iter().for_each(|photon| bounds.grow(&photon.point));
+        let extent = &bounds.max - &bounds.min;
+        let axis = if extent.x >= extent.y && extent.x >= extent.z {
+            0
+        } else if extent.y >= extent.z {
+            1
+        } else {
+            2
+        };
+
+        let mid = photons.len() / 2;
+        photons.select_nth_unstable_by(mid, |a, b| {
+            coordinate(&a.point, axis).total_cmp(&coordinate(&b.point, axis))
+        });
+        axes[mid] = axis;
+
+        let (left, right) = photons.split_at_mut(mid);
+        let (left_axes, right_axes) = axes.split_at_mut(mid);
+        Self::build(left, left_axes);
+        Self::build(&mut right[1..], &mut right_axes[1..]);
+    }
+
+    /// Calls `found` with every photon within `radius` of the point
+    fn for_each_within(&self, point: &Vector3, radius: f64, found: &mut impl FnMut(&Photon)) {
+        self.search(0, self.photons.len(), point, radius * radius, found);
+    }
+
+    fn search(
+        &self,
+        start: usize,
+        end: usize,
+        point: &Vector3,
+        radius_squared: f64,
+        found: &mut impl FnMut(&Photon),
+    ) {
+        if start >= end {
+            return;
+        }
+        let mid = start + (end - start) / 2;
+        let photon = &self.photons[mid];
+        if (&photon.point - point).quadrance() <= radius_squared {
+            found(photon);
+        }
+
+        let axis = self.axes[mid];
+        let offset = coordinate(point, axis) - coordinate(&photon.point, axis);
+        let (near, far) = if offset < 0.0 {
+            ((start, mid), (mid + 1, end))
+        } else {
+            ((mid + 1, end), (start, mid))
+        };
+        self.search(near.0, near.1, point, radius_squared, found);
+        if offset * offset <= radius_squared {
+            self.search(far.0, far.1, point, radius_squared, found);
+        }
+    }
+}
+
+fn coordinate(v: &Vector3, axis: u8) -> f64 {
+    match axis {
+        0 => v.x,
+        1 => v.y,
+        _ => v.z,
+    }
+}
+
+/// Stochastic progressive photon mapping. Every pass follows one camera ray per pixel
+/// through mirrors and glass to a diffuse hit, traces a batch of photons from the light
+/// sources and gathers the ones landing near each hit, shrinking the radius pass by pass.
+pub(crate) struct PhotonMapper {
+    camera: Arc<Camera>,
+    sources: Sources,
+    /// Photons per pass
+    photons: u32,
+    /// Starting gather radius
+    radius: f64,
+}
+
+impl PhotonMapper {
+    pub(crate) fn new(world: &World, camera: Arc<Camera>, photons: u32, radius: f64) -> Self {
+        Self {
+            camera,
+            sources: Sources::new(world),
+            photons,
+            radius,
+        }
+    }
+
+    /// Linear color of every pixel, `progress` is called after each pass
+    pub(crate) fn render(
+        &self,
+        world: &World,
+        passes: u32,
+        max_depth: u32,
+        spectral: bool,
+        threads: usize,
+        progress: impl Fn(),
+    ) -> Vec<Vector3> {
+        let pixel_count = self.camera.pixel_count() as usize;
+        let mut estimates = vec![
+            PixelEstimate {
+                radius: self.radius,
+                count: 0.0,
+                flux: Vector3::fill(0.0),
+                direct: Vector3::fill(0.0),
+            };
+            pixel_count
+        ];
+        let chunk = pixel_count.div_ceil(threads).max(1);
+        let per_thread = self.photons.div_ceil(threads as u32);
+
+        for pass in 0..passes {
+            // every pixel and photon of a pass shares the wavelengths
+            let wavelengths =
+                spectral.then(|| spectrum::sample_wavelengths_stratified(pass, passes));
+            let wavelengths = wavelengths.as_ref();
+
+            let visible: Vec<Option<VisiblePoint>> = thread::scope(|scope| {
+                let handles: Vec<_> = estimates
+                    .chunks_mut(chunk)
+                    .enumerate()
+                    .map(|(index, estimates)| {
+                        scope.spawn(move || {
+                            estimates
+                                .iter_mut()
+                                .enumerate()
+                                .map(|(offset, estimate)| {
+                                    let pixel = (index * chunk + offset) as u32;
+                                    self.trace_camera(
+                                        world,
+                                        pixel,
+                                        max_depth,
+                                        wavelengths,
+                                        estimate,
+                                    )
+                                })
+                                .collect::<Vec<_>>()
+                        })
+                    })
+                    .collect();
+                handles
+                    .into_iter()
+                    .flat_map(|h| h.join().unwrap())
+                    .collect()
+            });
+
+            let photons: Vec<Photon> = thread::scope(|scope| {
+                let handles: Vec<_> = (0..threads as u32)
+                    .map(|index| {
+                        let count = per_thread.min(self.photons.saturating_sub(index * per_thread));
+                        scope.spawn(move || {
+                            let mut photons = Vec::new();
+                            for _ in 0..count {
+                                self.trace_photon(world, max_depth, wavelengths, &mut photons);
+                            }
+                            photons
+                        })
+                    })
+                    .collect();
+                handles
+                    .into_iter()
+                    .flat_map(|h| h.join().unwrap())
+                    .collect()
+            });
+            let tree = KdTree::new(photons);
+
+            thread::scope(|scope| {
+                for (estimates, visible) in estimates.chunks_mut(chunk).zip(visible.chunks(chunk)) {
+                    let tree = &tree;
+                    scope.spawn(move || {
+                        for (estimate, visible) in estimates.iter_mut().zip(visible) {
+                            if let Some(visible) = visible {
+                                gather(tree, visible, estimate, wavelengths);
+                            }
+                        }
+                    });
+                }
+            });
+
+            progress();
+        }
+
+        let emitted = passes as f64 * self.photons as f64;
+        estimates
+            .into_iter()
+            .map(|estimate| {
+                let area = PI * estimate.radius * estimate.radius;
+                estimate.direct / passes + estimate.flux / (emitted * area)
+            })
+            .collect()
+    }
+
+    /// Follows a camera ray to its first diffuse hit, adding what it finds on the way
+    fn trace_camera(
+        &self,
+        world: &World,
+        pixel: u32,
+        max_depth: u32,
+        wavelengths: Option<&[f64; 3]>,
+        estimate: &mut PixelEstimate,
+    ) -> Option<VisiblePoint> {
+        let mut ray = self.camera.sample_ray(pixel);
+        let mut beta = Vector3::fill(1.0);
+        let mut radiance = Vector3::fill(0.0);
+        let mut dispersed = false;
+        let mut visible = None;
+        // density of the last scattering in a medium, where the lights were sampled too
+        let mut scatter_pdf = None;
+
+        for _ in 0..max_depth {
+            let Some((object, hit)) = world.hit_object(&ray) else {
+                let value = world.background.value(&ray.direction);
+                radiance += &beta * upsample_traced(value, wavelengths);
+                break;
+            };
+            let material = hit.material;
+            let mut record = hit.record;
+            record.wavelengths = wavelengths.copied();
+            let weight = match (scatter_pdf, world.emitter_of(object)) {
+                (Some(pdf), Some(emitter)) => {
+                    let light_pdf = ray::emitter_pdf(world, emitter, &ray.origin, &record);
+                    ray::power_heuristic(pdf, light_pdf)
+                }
+                _ => 1.0,
+            };
+            radiance += &beta * upsample_traced(material.emit(&record) * weight, wavelengths);
+
+            if material.volumetric() {
+                // media hold no photons, so they sample the lights and the ray scatters on
+                let value = ray::direct_light(&record, &material, world);
+                radiance += &beta * upsample_traced(value, wavelengths);
+            } else if !material.is_delta(&record) {
+                radiance += &beta * background_light(&record, &material, world, wavelengths);
+                visible = Some(VisiblePoint {
+                    record,
+                    material,
+                    beta,
+                    dispersed,
+                });
+                break;
+            }
+
+            let sampled_record = record.clone();
+            let Some(scattered) = material.scatter(record) else {
+                break;
+            };
+            scatter_pdf = if material.volumetric() {
+                material.pdf(&sampled_record, &scattered.ray.direction.normal())
+            } else {
+                None
+            };
+            let mut value = upsample_traced(scattered.attenuation, wavelengths);
+            if scattered.dispersed && !dispersed && wavelengths.is_some() {
+                dispersed = true;
+                value *= Vector3::new(3.0, 0.0, 0.0);
+            }
+            beta *= value;
+            ray = scattered.ray;
+        }
+
+        estimate.direct += match wavelengths {
+            Some(wavelengths) => spectrum::to_rgb(&radiance, wavelengths),
+            None => radiance,
+        };
+        visible
+    }
+
+    /// Leaves a photon at every diffuse surface hit of a path from a light source
+    fn trace_photon(
+        &self,
+        world: &World,
+        max_depth: u32,
+        wavelengths: Option<&[f64; 3]>,
+        photons: &mut Vec<Photon>,
+    ) {
+        let Some(emission) = self.sources.emit(world, wavelengths) else {
+            return;
+        };
+        let mut power = emission.power();
+        let mut ray = emission.ray;
+        let mut dispersed = false;
+
+        for _ in 0..max_depth {
+            let Some(hit) = world.hit(&ray) else {
+                break;
+            };
+            let material = hit.material;
+            let mut record = hit.record;
+            record.wavelengths = wavelengths.copied();
+
+            if !material.is_delta(&record) && !material.volumetric() {
+                photons.push(Photon {
+                    point: record.point.clone(),
+                    direction: ray.direction.normal(),
+                    power: power.clone(),
+                    dispersed,
+                });
+            }
+
+            let Some(scattered) = material.scatter(record) else {
+                break;
+            };
+            let mut value = upsample_traced(scattered.attenuation, wavelengths);
+            if scattered.dispersed && !dispersed && wavelengths.is_some() {
+                dispersed = true;
+                value *= Vector3::new(3.0, 0.0, 0.0);
+            }
+            power *= value;
+            ray = scattered.ray;
+        }
+    }
+}
+
+/// Distant lights and the background lighting a visible point. Photons don't come from
+/// them, so the background is also reached by scattering once more.
+fn background_light(
+    record: &HitRecord,
+    material: &MaterialRef,
+    world: &World,
+    wavelengths: Option<&[f64; 3]>,
+) -> Vector3 {
+    let sampled = ray::distant_light(record, material, world)
+        + ray::environment_light(record, material, world);
+    let mut total = upsample_traced(sampled, wavelengths);
+
+    if let Some(scattered) = material.scatter(record.clone()) {
+        if world.hit(&scattered.ray).is_none() {
+            let direction = scattered.ray.direction.normal();
+            let weight = match material.pdf(record, &direction) {
+                Some(pdf) => ray::power_heuristic(pdf, world.background.pdf(&direction)),
+                None => 1.0,
+            };
+            let value = world.background.value(&direction) * weight;
+            total += upsample_traced(scattered.attenuation, wavelengths)
+                * upsample_traced(value, wavelengths);
+        }
+    }
+    total
+}
+
+/// Adds the photons around the visible point to the pixel and shrinks its radius
+fn gather(
+    tree: &KdTree,
+    visible: &VisiblePoint,
+    estimate: &mut PixelEstimate,
+    wavelengths: Option<&[f64; 3]>,
+) {
+    let VisiblePoint {
+        record,
+        material,
+        beta,
+        dispersed,
+    } = visible;
+    let mut flux = Vector3::fill(0.0);
+    let mut found = 0;
+    tree.for_each_within(&record.point, estimate.radius, &mut |photon| {
+        found += 1;
+        let direction = -&photon.direction;
+        let Some(value) = material.eval(record, &direction) else {
+            return;
+        };
+        // the flux already stands for the projected area
+        let cosine = direction.dot(&record.shading_normal).abs();
+        if cosine <= 1e-6 {
+            return;
+        }
+        let mut value = upsample_traced(value / cosine, wavelengths) * &photon.power;
+        if *dispersed && photon.dispersed {
+            // both picked the same hero wavelength
+            value = value / 3.0;
+        }
+        flux += value;
+    });
+    if found == 0 {
+        return;
+    }
+
+    let flux = beta * flux;
+    let flux = match wavelengths {
+        Some(wavelengths) => spectrum::to_rgb(&flux, wavelengths),
+        None => flux,
+    };
+    let count = estimate.count + ALPHA * found as f64;
+    let radius = estimate.radius * (count / (estimate.count + found as f64)).sqrt();
+    let shrink = (radius / estimate.radius).powi(2);
+    estimate.flux = (&estimate.flux + flux) * shrink;
+    estimate.count = count;
+    estimate.radius = radius;
+}
@@ -1,6 +1,5 @@
 use std::{
     future::Future,
-    iter::zip,
     pin::Pin,
     sync::{Arc, OnceLock, RwLock},
     task::{Context, Poll},
@@ -8,9 +7,14 @@ use std::{
 
 use crossbeam::deque::Injector;
 
-use crate::math::Vector3;
+use crate::{geometry::T_EPSILON, light::LightRef, material::MaterialRef, math::Vector3, spectrum};
 
-use super::hit::Hit;
+use super::{
+    bidirectional::Bidirectional,
+    debug::View,
+    hit::{Hit, HitRecord},
+    world::World,
+};
 
 pub struct Ray {
     pub origin: Vector3,
@@ -32,9 +36,16 @@ pub(crate) struct RayCast {
     buf_idx: u32,
     samp_idx: usize,
     depth: u32,
-    colors: Vec<Vector3>,
-    attenuations: Vec<Option<Vector3>>,
-    background: Arc<Vector3>,
+    /// Product of the attenuations so far
+    throughput: Vector3,
+    radiance: Vector3,
+    /// Set when tracing spectrally, colors are then values at these wavelengths
+    wavelengths: Option<[f64; 3]>,
+    /// Only the hero wavelength is still carried
+    dispersed: bool,
+    /// Density the last bounce picked its direction with, when the background was
+    /// also sampled from there
+    scatter_pdf: Option<f64>,
     result: Arc<RwLock<OnceLock<Vector3>>>,
 }
 
@@ -44,7 +55,7 @@ impl RayCast {
         buf_idx: u32,
         samp_idx: usize,
         depth: u32,
-        background: Arc<Vector3>,
+        spectral: bool,
         result: Arc<RwLock<OnceLock<Vector3>>>,
     ) -> Self {
         Self {
@@ -52,64 +63,268 @@ impl RayCast {
             buf_idx,
             samp_idx,
             depth,
-            colors: Vec::new(),
-            attenuations: Vec::new(),
-            background,
+            throughput: Vector3::fill(1.0),
+            radiance: Vector3::fill(0.0),
+            wavelengths: spectral.then(spectrum::sample_wavelengths),
+            dispersed: false,
+            scatter_pdf: None,
             result,
         }
     }
 
-    pub(crate) fn resolve_hit(mut self, opt_hit: Option<Hit>) -> Option<Self> {
-        let mut color = None;
-        let mut attenuation = None;
-
+    /// Takes the closest hit with the index of its object
+    pub(crate) fn resolve_hit(
+        mut self,
+        opt_hit: Option<(usize, Hit)>,
+        world: &World,
+    ) -> Option<Self> {
         match opt_hit {
-            Some(hit) => {
+            Some((object, hit)) => {
                 let material = hit.material;
-                let record = hit.record;
-                color = Some(material.emit());
+                let mut record = hit.record;
+                record.wavelengths = self.wavelengths;
+
+                // emitters were also sampled from the last bounce
+                let weight = match (self.scatter_pdf, world.emitter_of(object)) {
+                    (Some(pdf), Some(emitter)) => {
+                        let light_pdf = emitter_pdf(world, emitter, &self.ray.origin, &record);
+                        power_heuristic(pdf, light_pdf)
+                    }
+                    _ => 1.0,
+                };
+                let emitted = material.emit(&record) * weight
+                    + direct_light(&record, &material, world)
+                    + environment_light(&record, &material, world);
+                self.radiance += &self.throughput * self.spectral_value(emitted);
 
+                let sampled_record = record.clone();
                 match material.scatter(record) {
                     Some(scattered) => {
-                        attenuation = Some(scattered.attenuation);
+                        self.scatter_pdf =
+                            material.pdf(&sampled_record, &scattered.ray.direction.normal());
+                        let mut value = self.spectral_value(scattered.attenuation);
+                        if scattered.dispersed && !self.dispersed && self.wavelengths.is_some() {
+                            // every wavelength is uniform on its own, so the hero stands
+                            // in for all three
+                            self.dispersed = true;
+                            value *= Vector3::new(3.0, 0.0, 0.0);
+                        }
+                        self.throughput *= value;
                         self.ray = scattered.ray;
                         self.depth -= 1;
                     }
                     None => self.depth = 0,
                 }
             }
-            None => self.depth = 0,
+            None => {
+                let direction = &self.ray.direction;
+                let weight = match self.scatter_pdf {
+                    Some(pdf) => power_heuristic(pdf, world.background.pdf(direction)),
+                    None => 1.0,
+                };
+                let value = world.background.value(direction) * weight;
+                self.radiance += &self.throughput * self.spectral_value(value);
+                self.depth = 0;
+            }
         }
 
-        match color {
-            Some(c) => self.colors.push(c),
-            None => self.colors.push(self.background.as_ref().clone()),
+        if self.depth == 0 {
+            self.finish();
+            return None;
         }
-        self.attenuations.push(attenuation);
 
-        if self.depth == 0 {
-            let result = zip(self.colors, self.attenuations).rev().fold(
-                Vector3::fill(0.0),
-                |acc, (color, attenuation)| match attenuation {
-                    Some(attenuation) => (acc + color) * attenuation,
-                    None => acc + color,
-                },
-            );
-
-            if let Ok(guard) = self.result.read() {
-                if let Ok(()) = guard.set(result) {
-                    return None;
-                }
+        Some(self)
+    }
+
+    /// Traces the whole sample at once, with a light subpath for the camera subpath
+    pub(crate) fn bidirectional(mut self, bidirectional: &Bidirectional, world: &World) {
+        self.radiance =
+            bidirectional.radiance(&self.ray, self.depth, self.wavelengths.as_ref(), world);
+        self.finish();
+    }
+
+    /// Colors the sample by a debug view, always in RGB
+    pub(crate) fn inspect(mut self, view: &View, world: &World) {
+        self.radiance = view.value(&self.ray, self.depth, world);
+        self.wavelengths = None;
+        self.finish();
+    }
+
+    fn finish(self) {
+        let result = match &self.wavelengths {
+            Some(wavelengths) => spectrum::to_rgb(&self.radiance, wavelengths),
+            None => self.radiance,
+        };
+
+        if let Ok(guard) = self.result.read() {
+            if let Ok(()) = guard.set(result) {
+                return;
             }
+        }
 
-            eprintln!(
-                "error setting result for pixel #{} - sample #{}",
-                self.buf_idx, self.samp_idx
-            );
-            return None;
+        eprintln!(
+            "error setting result for pixel #{} - sample #{}",
+            self.buf_idx, self.samp_idx
+        );
+    }
+
+    /// RGB colors become their values at the traced wavelengths
+    fn spectral_value(&self, rgb: Vector3) -> Vector3 {
+        spectrum::upsample_traced(rgb, self.wavelengths.as_ref())
+    }
+}
+
+/// Light reaching the hit straight from the sampled lights and emitters that aren't
+/// blocked
+pub(super) fn direct_light(record: &HitRecord, material: &MaterialRef, world: &World) -> Vector3 {
+    let mut total = distant_light(record, material, world);
+    let lights = world.lights.len();
+    match &world.light_sampler {
+        Some(sampler) => {
+            if let Some((index, probability)) = sampler.pick(&record.point) {
+                total += if index < lights {
+                    light_contribution(&world.lights[index], record, material, world) / probability
+                } else {
+                    emitter_contribution(index - lights, record, material, world)
+                };
+            }
+        }
+        None => {
+            for light in world.lights.iter() {
+                total += light_contribution(light, record, material, world);
+            }
+            for emitter in 0..world.emitters.len() {
+                total += emitter_contribution(emitter, record, material, world);
+            }
         }
+    }
+    total
+}
 
-        Some(self)
+/// Light reaching the hit from the lights infinitely far away
+pub(super) fn distant_light(record: &HitRecord, material: &MaterialRef, world: &World) -> Vector3 {
+    world
+        .distant_lights
+        .iter()
+        .fold(Vector3::fill(0.0), |total, light| {
+            total + light_contribution(light, record, material, world)
+        })
+}
+
+fn light_contribution(
+    light: &LightRef,
+    record: &HitRecord,
+    material: &MaterialRef,
+    world: &World,
+) -> Vector3 {
+    let zero = Vector3::fill(0.0);
+    let Some(sample) = light.sample(&record.point) else {
+        return zero;
+    };
+    let Some(value) = material.eval(record, &sample.direction) else {
+        return zero;
+    };
+    let shadow = Ray::new(record.point.clone(), sample.direction);
+    let transmittance = world.transmittance(&shadow, sample.distance);
+    if transmittance <= 0.0 {
+        return zero;
+    }
+    value * sample.irradiance * transmittance
+}
+
+/// Light from a point picked on the surface of an emitter, weighted against the
+/// material scattering into the emitter by itself
+fn emitter_contribution(
+    emitter: usize,
+    record: &HitRecord,
+    material: &MaterialRef,
+    world: &World,
+) -> Vector3 {
+    let zero = Vector3::fill(0.0);
+    let Some(hit) = world.objects[world.emitters[emitter].object].sample_emitting() else {
+        return zero;
+    };
+    let offset = &hit.record.point - &record.point;
+    let distance = offset.len();
+    if distance <= T_EPSILON {
+        return zero;
+    }
+    let direction = offset / distance;
+    let (Some(scatter_pdf), Some(value)) = (
+        material.pdf(record, &direction),
+        material.eval(record, &direction),
+    ) else {
+        return zero;
+    };
+
+    let mut emitting = hit.record.arriving(&direction);
+    emitting.wavelengths = record.wavelengths;
+    let emitted = hit.material.emit(&emitting);
+    let light_pdf = emitter_pdf(world, emitter, &record.point, &emitting);
+    if emitted.near_zero() || light_pdf <= 0.0 {
+        return zero;
+    }
+    let shadow = Ray::new(record.point.clone(), direction);
+    let transmittance = world.transmittance(&shadow, distance - T_EPSILON);
+    if transmittance <= 0.0 {
+        return zero;
+    }
+    let weight = power_heuristic(light_pdf, scatter_pdf) * transmittance / light_pdf;
+    value * emitted * weight
+}
+
+/// Density over the sphere around `from` of sampling the emitter point, the chance of
+/// the emitter included
+pub(super) fn emitter_pdf(
+    world: &World,
+    emitter: usize,
+    from: &Vector3,
+    record: &HitRecord,
+) -> f64 {
+    let offset = &record.point - from;
+    let distance_squared = offset.quadrance();
+    let cos_theta = record.normal.dot(&offset).abs() / distance_squared.sqrt();
+    let area = world.objects[world.emitters[emitter].object].emitting_area();
+    if cos_theta <= 0.0 || area <= 0.0 {
+        return 0.0;
+    }
+    let probability = world.light_probability(from, world.lights.len() + emitter);
+    probability * distance_squared / (cos_theta * area)
+}
+
+/// Light from the background picked by importance, weighted against the material
+/// scattering into the background by itself
+pub(super) fn environment_light(
+    record: &HitRecord,
+    material: &MaterialRef,
+    world: &World,
+) -> Vector3 {
+    let zero = Vector3::fill(0.0);
+    let Some(sample) = world.background.sample() else {
+        return zero;
+    };
+    let (Some(scatter_pdf), Some(value)) = (
+        material.pdf(record, &sample.direction),
+        material.eval(record, &sample.direction),
+    ) else {
+        return zero;
+    };
+    let shadow = Ray::new(record.point.clone(), sample.direction);
+    let transmittance = world.transmittance(&shadow, f64::INFINITY);
+    if transmittance <= 0.0 {
+        return zero;
+    }
+    let weight = power_heuristic(sample.pdf, scatter_pdf) * transmittance / sample.pdf;
+    value * sample.radiance * weight
+}
+
+/// Multiple importance sampling weight of a strategy with density `pdf` against `other`
+pub(super) fn power_heuristic(pdf: f64, other: f64) -> f64 {
+    let (a, b) = (pdf * pdf, other * other);
+    if b > 0.0 {
+        a / (a + b)
+    } else {
+        1.0
     }
 }
 
@@ -123,11 +338,11 @@ impl RayFut {
         buf_idx: u32,
         samp_idx: usize,
         depth: u32,
-        background: Arc<Vector3>,
+        spectral: bool,
         injector: Arc<Injector<RayCast>>,
     ) -> Self {
         let result = Arc::new(RwLock::new(OnceLock::new()));
-        let cast = RayCast::new(ray, buf_idx, samp_idx, depth, background, result.clone());
+        let cast = RayCast::new(ray, buf_idx, samp_idx, depth, spectral, result.clone());
         injector.push(cast);
 
         Self { result }
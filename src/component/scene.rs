@@ -7,102 +7,217 @@ use std::{
     thread::{self, JoinHandle},
 };
 
+use config::ConfigError;
 use crossbeam::deque::{Injector, Stealer, Worker};
 
 use crate::{
+    description::{gltf, SceneDescription},
     geometry::{sphere::Sphere, HittableRef},
     material::{diffuse_light::DiffuseLight, glass::Glass, lambert::Lambert, metal::Metal},
     math::{self, Vector3},
-    settings,
+    settings::{self, Integrator},
 };
 
-use super::ray::{Ray, RayCast, RayFut};
+use super::{
+    bidirectional::Bidirectional,
+    camera::Camera,
+    debug::View,
+    photon_map::PhotonMapper,
+    ray::{Ray, RayCast, RayFut},
+    world::World,
+};
 
 #[allow(dead_code)]
 pub(crate) struct Scene {
-    objects: Arc<Vec<HittableRef>>,
-    background: Arc<Vector3>,
+    world: Arc<World>,
+    pub(crate) camera: Arc<Camera>,
+    photon_mapper: Option<PhotonMapper>,
     pub(crate) thread_count: usize,
     injector: Arc<Injector<RayCast>>,
     is_running: Arc<AtomicBool>,
     handlers: Vec<JoinHandle<()>>,
 }
 
+/// How the workers finish the casts they take
+#[derive(Clone)]
+enum Tracer {
+    /// A bounce at a time, requeued in between
+    Path,
+    Bidirectional(Arc<Bidirectional>),
+    Debug(View),
+}
+
 impl Scene {
-    pub fn build(settings: Option<settings::Scene>) -> Self {
-        let _input_file = settings.map(|s| s.input).unwrap_or("".into());
+    /// glTF scenes with a camera override the camera settings
+    pub fn build(
+        settings: Option<settings::Scene>,
+        mut camera: settings::Camera,
+        image: &settings::Image,
+    ) -> Result<Self, ConfigError> {
+        let (input_file, background) = settings
+            .map(|s| (s.input, s.background))
+            .unwrap_or_default();
+
+        let (objects, mut lights, scene_background) = if input_file.is_empty() {
+            (random_scene(), Vec::new(), None)
+        } else if input_file.ends_with(".gltf") || input_file.ends_with(".glb") {
+            let scene = gltf::load(&input_file)?;
+            if let Some(pose) = scene.camera {
+                pose.apply(&mut camera);
+            }
+            (scene.objects, Vec::new(), None)
+        } else {
+            let mut description = SceneDescription::load(&input_file)?;
+            (
+                description.build_objects()?,
+                description.build_lights()?,
+                Some(description.background),
+            )
+        };
 
-        let objects = random_scene();
-        let objects = Arc::new(objects);
-        let background = Arc::new(Vector3::fill(0.0));
+        // the settings background replaces the one of the scene file
+        let (background, sun) = background
+            .or(scene_background)
+            .unwrap_or_default()
+            .build()?;
+        lights.extend(sun);
+        let world = Arc::new(World::new(objects, lights, background, image.light_sampler));
+
+        let camera = match image.integrator {
+            Integrator::Bdpt => Camera::build(camera).with_splats(),
+            _ => Camera::build(camera),
+        };
+        let camera = Arc::new(camera);
+        if matches!(image.integrator, Integrator::AmbientOcclusion) && image.ao_radius <= 0.0 {
+            return Err(ConfigError::Message("ao_radius must be positive".into()));
+        }
+        let view = View::new(image.integrator, image.ao_radius, camera.focus_distance());
+        let tracer = match (image.integrator, view) {
+            (Integrator::Bdpt, _) => {
+                Tracer::Bidirectional(Arc::new(Bidirectional::new(&world, camera.clone())))
+            }
+            (_, Some(view)) => Tracer::Debug(view),
+            _ => Tracer::Path,
+        };
+        let photon_mapper = match image.integrator {
+            Integrator::Sppm => {
+                let radius = image.photon_radius.unwrap_or(4.0 * camera.pixel_size());
+                if radius <= 0.0 {
+                    return Err(ConfigError::Message(
+                        "photon_radius must be positive".into(),
+                    ));
+                }
+                Some(PhotonMapper::new(
+                    &world,
+                    camera.clone(),
+                    image.photons,
+                    radius,
+                ))
+            }
+            _ => None,
+        };
 
         let thread_count = thread::available_parallelism().unwrap().get();
+        // the photon mapper runs its passes on threads of its own
+        let worker_count = match photon_mapper {
+            Some(_) => 0,
+            None => thread_count,
+        };
 
         let injector = Arc::new(Injector::new());
         let is_running = Arc::new(AtomicBool::new(true));
 
-        let mut workers = Vec::with_capacity(thread_count);
-        let mut stealers = Vec::with_capacity(thread_count);
+        let mut workers = Vec::with_capacity(worker_count);
+        let mut stealers = Vec::with_capacity(worker_count);
 
-        for _ in 0..thread_count {
+        for _ in 0..worker_count {
             let worker: Worker<RayCast> = Worker::new_fifo();
             stealers.push(worker.stealer());
             workers.push(worker);
         }
 
-        let mut handlers = Vec::with_capacity(thread_count);
+        let mut handlers = Vec::with_capacity(worker_count);
 
-        let batch_limit = thread_count / 2;
+        let batch_limit = (thread_count / 2).max(1);
         for (idx, worker) in workers.into_iter().enumerate() {
             let injector = injector.clone();
             let mut stealers = stealers.clone();
             stealers.remove(idx);
             let is_running = is_running.clone();
-            let objects = objects.clone();
+            let world = world.clone();
+            let tracer = tracer.clone();
 
             handlers.push(thread::spawn(move || {
                 while is_running.load(Ordering::Relaxed) {
                     let mut work = find_work(&worker, &injector, &stealers, batch_limit);
 
                     while let Some(cast) = work {
-                        let mut hit = None;
-                        let mut closest = f64::INFINITY;
-
-                        for obj in objects.iter() {
-                            if let Some(res) = obj.hit(&cast.ray) {
-                                if res.record.t < closest {
-                                    closest = res.record.t;
-                                    hit = Some(res);
-                                }
+                        work = match &tracer {
+                            Tracer::Path => {
+                                let hit = world.hit_object(&cast.ray);
+                                cast.resolve_hit(hit, &world)
                             }
-                        }
-
-                        work = cast.resolve_hit(hit);
+                            Tracer::Bidirectional(bidirectional) => {
+                                cast.bidirectional(bidirectional, &world);
+                                None
+                            }
+                            Tracer::Debug(view) => {
+                                cast.inspect(view, &world);
+                                None
+                            }
+                        };
                     }
                 }
             }));
         }
 
-        Self {
-            objects,
-            background,
+        Ok(Self {
+            world,
+            camera,
+            photon_mapper,
             thread_count,
             injector,
             is_running,
             handlers,
-        }
+        })
     }
 
-    pub fn cast(&self, ray: Ray, buf_idx: u32, samp_idx: usize, max_depth: u32) -> RayFut {
+    pub fn cast(
+        &self,
+        ray: Ray,
+        buf_idx: u32,
+        samp_idx: usize,
+        max_depth: u32,
+        spectral: bool,
+    ) -> RayFut {
         RayFut::new(
             ray,
             buf_idx,
             samp_idx,
             max_depth,
-            self.background.clone(),
+            spectral,
             self.injector.clone(),
         )
     }
+
+    /// Linear color of every pixel by photon mapping, `None` for the other integrators
+    pub fn render_photons(
+        &self,
+        passes: u32,
+        max_depth: u32,
+        spectral: bool,
+        progress: impl Fn(),
+    ) -> Option<Vec<Vector3>> {
+        let photon_mapper = self.photon_mapper.as_ref()?;
+        Some(photon_mapper.render(
+            &self.world,
+            passes,
+            max_depth,
+            spectral,
+            self.thread_count,
+            progress,
+        ))
+    }
 }
 
 impl Drop for Scene {
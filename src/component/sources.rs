@@ -0,0 +1,133 @@
+use std::f64::consts::PI;
+
+use crate::{
+    light::alias_table::AliasTable,
+    material::MaterialRef,
+    math::{self, Vector3},
+    spectrum,
+};
+
+use super::{hit::HitRecord, ray::Ray, world::World};
+
+/// Where paths traced from the lights start
+pub(crate) enum Source {
+    /// Index of an object with a light emitting material
+    Emitter(usize),
+    /// Index into the positioned lights of the world
+    Light(usize),
+}
+
+/// Light leaving a source in a random direction
+pub(crate) struct Emission {
+    pub(crate) source: usize,
+    /// Emitting surface with the normals on the side the light leaves, `None` for lights
+    /// at a single point
+    pub(crate) hit: Option<(HitRecord, MaterialRef)>,
+    /// Unit direction
+    pub(crate) ray: Ray,
+    /// Radiance of surfaces, intensity of lights at a point
+    pub(crate) emitted: Vector3,
+    /// Density of the origin over area times the chance of the source, just the chance
+    /// for lights at a point
+    pub(crate) pdf_position: f64,
+    pub(crate) pdf_direction: f64,
+    /// Between the ray and the surface normal, one for lights at a point
+    pub(crate) cos_theta: f64,
+}
+
+impl Emission {
+    /// Power carried by the ray
+    pub(crate) fn power(&self) -> Vector3 {
+        &self.emitted * (self.cos_theta / (self.pdf_position * self.pdf_direction))
+    }
+}
+
+/// Light emitting objects and positioned lights, picked in proportion to their power.
+/// The emitters of the world come first, under the same indices.
+pub(crate) struct Sources {
+    pub(crate) sources: Vec<Source>,
+    table: Option<AliasTable>,
+}
+
+impl Sources {
+    pub(crate) fn new(world: &World) -> Self {
+        let mut sources = Vec::new();
+        let mut powers = Vec::new();
+        for emitter in world.emitters.iter() {
+            sources.push(Source::Emitter(emitter.object));
+            powers.push(emitter.power);
+        }
+        for (index, light) in world.lights.iter().enumerate() {
+            sources.push(Source::Light(index));
+            powers.push(light.power());
+        }
+
+        Self {
+            table: AliasTable::from_weights(&powers),
+            sources,
+        }
+    }
+
+    /// Index of a source and the chance of picking it, `None` in scenes without light
+    pub(crate) fn pick(&self) -> Option<(usize, f64)> {
+        self.table.as_ref().map(AliasTable::sample)
+    }
+
+    pub(crate) fn probability(&self, source: usize) -> f64 {
+        self.table
+            .as_ref()
+            .map_or(0.0, |table| table.probability(source))
+    }
+
+    /// Ray leaving a source picked by power. Surfaces emit cosine weighted on either side.
+    pub(crate) fn emit(&self, world: &World, wavelengths: Option<&[f64; 3]>) -> Option<Emission> {
+        let (source, probability) = self.pick()?;
+        match self.sources[source] {
+            Source::Emitter(object) => {
+                let object = &world.objects[object];
+                let hit = object.sample_emitting()?;
+                let mut normal = hit.record.normal.clone();
+                if math::rand_f64() < 0.5 {
+                    normal = -normal;
+                }
+                let direction = cosine_direction(&normal);
+                let cos_theta = direction.dot(&normal);
+                let mut record = hit.record.arriving(&-&direction);
+                record.wavelengths = wavelengths.copied();
+
+                let emitted = spectrum::upsample_traced(hit.material.emit(&record), wavelengths);
+                let ray = Ray::new(record.point.clone(), direction);
+                Some(Emission {
+                    source,
+                    hit: Some((record, hit.material)),
+                    ray,
+                    emitted,
+                    pdf_position: probability / object.emitting_area(),
+                    pdf_direction: cos_theta / (2.0 * PI),
+                    cos_theta,
+                })
+            }
+            Source::Light(index) => {
+                let sample = world.lights[index].sample_emission()?;
+                Some(Emission {
+                    source,
+                    hit: None,
+                    ray: Ray::new(sample.origin, sample.direction),
+                    emitted: spectrum::upsample_traced(sample.intensity, wavelengths),
+                    pdf_position: probability,
+                    pdf_direction: sample.pdf,
+                    cos_theta: 1.0,
+                })
+            }
+        }
+    }
+}
+
+/// Unit direction around `normal` with density cosine over pi
+pub(super) fn cosine_direction(normal: &Vector3) -> Vector3 {
+    let direction = normal + Vector3::random_unit();
+    if direction.near_zero() {
+        return normal.clone();
+    }
+    direction.normal()
+}
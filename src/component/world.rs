@@ -0,0 +1,185 @@
+use std::{collections::HashMap, f64::consts::PI, sync::Arc};
+
+use crate::{
+    background::BackgroundRef,
+    geometry::{aabb::Aabb, HittableRef, T_EPSILON},
+    light::{alias_table::AliasTable, bvh::LightBvh, LightBounds, LightRef, LightSamplerRef},
+    material::MaterialRef,
+    math::{Interval, Vector3},
+    settings::LightSampling,
+};
+
+use super::{hit::Hit, ray::Ray};
+
+/// Emission samples per object to estimate its power and bounds
+const EMITTER_SAMPLES: usize = 16;
+
+/// Object with a light emitting material
+pub(crate) struct Emitter {
+    pub(crate) object: usize,
+    /// Luminance of the power leaving both sides of the surface
+    pub(crate) power: f64,
+    /// Around the sampled points of the surface
+    pub(crate) bounds: Aabb,
+}
+
+/// Everything the rays can meet, shared by the worker threads
+pub(crate) struct World {
+    pub(crate) objects: Vec<HittableRef>,
+    /// Only reachable by sampling them explicitly
+    pub(crate) lights: Vec<LightRef>,
+    /// Lights infinitely far away, always sampled
+    pub(crate) distant_lights: Vec<LightRef>,
+    /// Objects emitting light, sampled like the lights
+    pub(crate) emitters: Vec<Emitter>,
+    /// Emitter of every object
+    emitter_of: Vec<Option<usize>>,
+    /// Picks one of the `lights`, or of the `emitters` after them, per shading point,
+    /// every one is sampled without it
+    pub(crate) light_sampler: Option<LightSamplerRef>,
+    pub(crate) background: BackgroundRef,
+    /// Index of every material by its address, numbered in the order of the objects
+    material_ids: HashMap<usize, usize>,
+}
+
+impl World {
+    pub(crate) fn new(
+        objects: Vec<HittableRef>,
+        lights: Vec<LightRef>,
+        background: BackgroundRef,
+        light_sampling: LightSampling,
+    ) -> Self {
+        let (lights, distant_lights): (Vec<LightRef>, Vec<LightRef>) =
+            lights.into_iter().partition(|l| l.position().is_some());
+        let emitters: Vec<Emitter> = objects
+            .iter()
+            .enumerate()
+            .filter_map(|(index, object)| Emitter::new(index, object))
+            .collect();
+        let mut emitter_of = vec![None; objects.len()];
+        for (index, emitter) in emitters.iter().enumerate() {
+            emitter_of[emitter.object] = Some(index);
+        }
+
+        let bounds: Vec<LightBounds> = lights
+            .iter()
+            .map(|light| {
+                light
+                    .bounds()
+                    .unwrap_or_else(|| LightBounds::uniform(Aabb::empty(), 0.0))
+            })
+            .chain(
+                emitters
+                    .iter()
+                    .map(|e| LightBounds::uniform(e.bounds.clone(), e.power)),
+            )
+            .collect();
+        let light_sampler: Option<LightSamplerRef> = match light_sampling {
+            LightSampling::All => None,
+            LightSampling::Power => AliasTable::new(&bounds).map(|s| Box::new(s) as _),
+            LightSampling::Bvh => LightBvh::new(&bounds).map(|s| Box::new(s) as _),
+        };
+        let mut material_ids = HashMap::new();
+        for object in objects.iter() {
+            object.for_each_material(&mut |material| {
+                let next = material_ids.len();
+                material_ids.entry(address(material)).or_insert(next);
+            });
+        }
+
+        Self {
+            objects,
+            lights,
+            distant_lights,
+            emitters,
+            emitter_of,
+            light_sampler,
+            background,
+            material_ids,
+        }
+    }
+
+    /// Same for the same scene on every run
+    pub(crate) fn material_id(&self, material: &MaterialRef) -> Option<usize> {
+        self.material_ids.get(&address(material)).copied()
+    }
+
+    /// Index of the emitter made of the object, if it emits anything
+    pub(crate) fn emitter_of(&self, object: usize) -> Option<usize> {
+        self.emitter_of[object]
+    }
+
+    /// Chance of sampling the light at `index`, or the emitter at `index` past the
+    /// lights, from the point
+    pub(crate) fn light_probability(&self, point: &Vector3, index: usize) -> f64 {
+        self.light_sampler
+            .as_ref()
+            .map_or(1.0, |sampler| sampler.probability(point, index))
+    }
+
+    pub(crate) fn hit(&self, ray: &Ray) -> Option<Hit> {
+        self.hit_object(ray).map(|(_, hit)| hit)
+    }
+
+    /// Closest hit and the index of the object it belongs to
+    pub(crate) fn hit_object(&self, ray: &Ray) -> Option<(usize, Hit)> {
+        let mut hit = None;
+        let mut closest = Interval::new(T_EPSILON, f64::INFINITY);
+
+        // Every hit shrinks the range, so farther objects are rejected early
+        for (index, obj) in self.objects.iter().enumerate() {
+            if let Some(res) = obj.hit(ray, closest) {
+                closest.max = res.record.t;
+                hit = Some((index, res));
+            }
+        }
+        hit
+    }
+
+    /// Whether anything lies on the ray before `t_max`
+    pub(crate) fn occluded(&self, ray: &Ray, t_max: f64) -> bool {
+        self.objects.iter().any(|obj| obj.occluded(ray, t_max))
+    }
+
+    /// Share of the light getting through everything on the ray before `t_max`
+    pub(crate) fn transmittance(&self, ray: &Ray, t_max: f64) -> f64 {
+        let mut transmittance = 1.0;
+        for obj in self.objects.iter() {
+            transmittance *= obj.transmittance(ray, t_max);
+            if transmittance <= 0.0 {
+                break;
+            }
+        }
+        transmittance
+    }
+}
+
+fn address(material: &MaterialRef) -> usize {
+    Arc::as_ptr(material) as *const () as usize
+}
+
+impl Emitter {
+    /// `None` when the object emits nothing
+    fn new(object: usize, hittable: &HittableRef) -> Option<Self> {
+        let area = hittable.emitting_area();
+        if area <= 0.0 {
+            return None;
+        }
+        let mut bounds = Aabb::empty();
+        // radiance leaving both sides
+        let radiance = (0..EMITTER_SAMPLES)
+            .filter_map(|_| hittable.sample_emitting())
+            .map(|hit| {
+                bounds.grow(&hit.record.point);
+                let back = hit.record.arriving(&-&hit.record.direction);
+                hit.material.emit(&hit.record).luminance() + hit.material.emit(&back).luminance()
+            })
+            .sum::<f64>()
+            / EMITTER_SAMPLES as f64;
+        (radiance > 0.0).then_some(Self {
+            object,
+            power: PI * area * radiance,
+            bounds,
+        })
+    }
+}
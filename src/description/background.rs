@@ -0,0 +1,138 @@
+use config::ConfigError;
+use serde::Deserialize;
+
+use crate::{
+    background::{
+        constant::Constant, environment::EnvironmentMap, gradient::Gradient, sky::Sky,
+        BackgroundRef,
+    },
+    light::LightRef,
+    math::Vector3,
+};
+
+/// Either a plain `[r, g, b]` color or a table with a `type`
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub(crate) enum BackgroundDescription {
+    Color(Vector3),
+    Background(BackgroundKind),
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub(crate) enum BackgroundKind {
+    /// Latitude-longitude `.hdr` or `.exr` image, turned by `rotation` degrees around
+    /// the vertical axis and scaled by `intensity`
+    Environment {
+        file: String,
+        #[serde(default)]
+        rotation: f64,
+        #[serde(default = "default_intensity")]
+        intensity: f64,
+    },
+    /// Blend from `bottom` (default white) straight down to `top` (default light blue)
+    /// straight up
+    Gradient {
+        #[serde(default = "default_top")]
+        top: Vector3,
+        #[serde(default = "default_bottom")]
+        bottom: Vector3,
+    },
+    /// Preetham daylight sky for a sun at `elevation` degrees above the horizon and
+    /// `azimuth` degrees from -z towards +x, with a zenith of `intensity`. Unless `sun`
+    /// is false a matching directional light, scaled by `sun_intensity`, is added.
+    Sky {
+        #[serde(default = "default_elevation")]
+        elevation: f64,
+        #[serde(default)]
+        azimuth: f64,
+        #[serde(default = "default_turbidity")]
+        turbidity: f64,
+        #[serde(default = "default_intensity")]
+        intensity: f64,
+        #[serde(default = "default_sun")]
+        sun: bool,
+        #[serde(default = "default_intensity")]
+        sun_intensity: f64,
+    },
+}
+
+fn default_intensity() -> f64 {
+    1.0
+}
+
+fn default_top() -> Vector3 {
+    Vector3::new(0.5, 0.7, 1.0)
+}
+
+fn default_bottom() -> Vector3 {
+    Vector3::fill(1.0)
+}
+
+fn default_elevation() -> f64 {
+    45.0
+}
+
+fn default_turbidity() -> f64 {
+    2.5
+}
+
+fn default_sun() -> bool {
+    true
+}
+
+impl Default for BackgroundDescription {
+    fn default() -> Self {
+        Self::Color(Vector3::fill(0.0))
+    }
+}
+
+impl BackgroundDescription {
+    /// The background and the sun that comes with a sky
+    pub(crate) fn build(self) -> Result<(BackgroundRef, Option<LightRef>), ConfigError> {
+        let background: BackgroundRef = match self {
+            Self::Color(color) => Box::new(Constant::new(color)),
+            Self::Background(BackgroundKind::Environment {
+                file,
+                rotation,
+                intensity,
+            }) => Box::new(
+                EnvironmentMap::open(&file, rotation.to_radians(), intensity).map_err(|e| {
+                    ConfigError::Message(format!("failed to load environment {file}: {e}"))
+                })?,
+            ),
+            Self::Background(BackgroundKind::Gradient { top, bottom }) => {
+                Box::new(Gradient::new(top, bottom))
+            }
+            Self::Background(BackgroundKind::Sky {
+                elevation,
+                azimuth,
+                turbidity,
+                intensity,
+                sun,
+                sun_intensity,
+            }) => {
+                if !(0.0..=90.0).contains(&elevation) {
+                    return Err(ConfigError::Message(
+                        "sky elevation must be within 0 and 90 degrees".into(),
+                    ));
+                }
+                if !(1.7..=10.0).contains(&turbidity) {
+                    return Err(ConfigError::Message(
+                        "sky turbidity must be within 1.7 and 10".into(),
+                    ));
+                }
+                let sky = Sky::new(
+                    elevation.to_radians(),
+                    azimuth.to_radians(),
+                    turbidity,
+                    intensity,
+                );
+                let light: Option<LightRef> =
+                    sun.then(|| Box::new(sky.sun_light(sun_intensity)) as LightRef);
+                return Ok((Box::new(sky), light));
+            }
+        };
+        Ok((background, None))
+    }
+}
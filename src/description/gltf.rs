@@ -0,0 +1,355 @@
+use std::sync::Arc;
+
+use config::ConfigError;
+use gltf::{camera::Projection, image::Format, material::AlphaMode, mesh::Mode, Document, Node};
+use image::Rgb32FImage;
+
+use crate::{
+    geometry::{
+        alpha_mask::AlphaMask,
+        instance::Instance,
+        mesh::{Mesh, MeshData},
+        Hittable, HittableRef,
+    },
+    material::{diffuse_light::DiffuseLight, principled::Principled, sheen::Sheen, MaterialRef},
+    math::{Matrix4, Vector3},
+    settings,
+    texture::{image_texture::ImageTexture, solid_color::SolidColor, TextureRef},
+};
+
+/// Where the first camera in the glTF scene looks from
+pub(crate) struct CameraPose {
+    look_from: Vector3,
+    look_at: Vector3,
+    vup: Vector3,
+    vertical_fov: f64,
+    aspect_ratio: Option<f64>,
+}
+
+impl CameraPose {
+    pub(crate) fn apply(self, camera: &mut settings::Camera) {
+        camera.look_from = self.look_from;
+        camera.look_at = self.look_at;
+        camera.vup = self.vup;
+        camera.vertical_fov = self.vertical_fov;
+        if let Some(aspect_ratio) = self.aspect_ratio {
+            camera.aspect_height = aspect_ratio;
+            camera.aspect_width = 1.0;
+        }
+    }
+}
+
+pub(crate) struct GltfScene {
+    pub(crate) objects: Vec<HittableRef>,
+    pub(crate) camera: Option<CameraPose>,
+}
+
+struct Loader {
+    document: Document,
+    buffers: Vec<gltf::buffer::Data>,
+    images: Vec<gltf::image::Data>,
+    /// Every primitive of each mesh, shared by all the nodes using it
+    meshes: Vec<Option<Vec<Arc<dyn Hittable>>>>,
+    objects: Vec<HittableRef>,
+    camera: Option<CameraPose>,
+}
+
+pub(crate) fn load(file: &str) -> Result<GltfScene, ConfigError> {
+    let (document, buffers, images) = gltf::import(file)
+        .map_err(|e| ConfigError::Message(format!("failed to load glTF {file}: {e}")))?;
+
+    let mut loader = Loader {
+        meshes: vec![None; document.meshes().len()],
+        document,
+        buffers,
+        images,
+        objects: Vec::new(),
+        camera: None,
+    };
+
+    let document = loader.document.clone();
+    let scene = document
+        .default_scene()
+        .or_else(|| document.scenes().next())
+        .ok_or_else(|| ConfigError::Message(format!("glTF {file} has no scenes")))?;
+    for node in scene.nodes() {
+        loader.node(&node, &Matrix4::identity())?;
+    }
+
+    Ok(GltfScene {
+        objects: loader.objects,
+        camera: loader.camera,
+    })
+}
+
+impl Loader {
+    fn node(&mut self, node: &Node, parent: &Matrix4) -> Result<(), ConfigError> {
+        let to_world = parent * &Matrix4::from_columns(node.transform().matrix());
+
+        if let Some(mesh) = node.mesh() {
+            for primitive in self.mesh(&mesh)? {
+                // singular transforms flatten the mesh to nothing, skip it
+                if let Some(instance) = Instance::new(primitive, to_world.clone()) {
+                    self.objects.push(instance);
+                }
+            }
+        }
+
+        if let (Some(camera), None) = (node.camera(), &self.camera) {
+            if let Projection::Perspective(perspective) = camera.projection() {
+                // glTF cameras look down -z with +y up
+                let look_from = to_world.transform_point(&Vector3::fill(0.0));
+                let forward = to_world.transform_vector(&Vector3::new(0.0, 0.0, -1.0));
+                self.camera = Some(CameraPose {
+                    look_at: &look_from + forward,
+                    look_from,
+                    vup: to_world.transform_vector(&Vector3::new(0.0, 1.0, 0.0)),
+                    vertical_fov: (perspective.yfov() as f64).to_degrees(),
+                    aspect_ratio: perspective.aspect_ratio().map(|a| a as f64),
+                });
+            }
+        }
+
+        for child in node.children() {
+            self.node(&child, &to_world)?;
+        }
+        Ok(())
+    }
+
+    fn mesh(&mut self, mesh: &gltf::Mesh) -> Result<Vec<Arc<dyn Hittable>>, ConfigError> {
+        if let Some(primitives) = &self.meshes[mesh.index()] {
+            return Ok(primitives.clone());
+        }
+
+        let mut primitives = Vec::<Arc<dyn Hittable>>::new();
+        for primitive in mesh.primitives() {
+            if primitive.mode() != Mode::Triangles {
+                return Err(ConfigError::Message(format!(
+                    "unsupported primitive mode {:?} in mesh {}, only triangles are supported",
+                    primitive.mode(),
+                    mesh.index()
+                )));
+            }
+
+            let reader = primitive.reader(|buffer| Some(&self.buffers[buffer.index()]));
+            let mut data = MeshData {
+                positions: reader
+                    .read_positions()
+                    .ok_or_else(|| ConfigError::Message("primitive without positions".into()))?
+                    .map(|[x, y, z]| Vector3::new(x as f64, y as f64, z as f64))
+                    .collect(),
+                normals: reader.read_normals().map(|normals| {
+                    normals
+                        .map(|[x, y, z]| Vector3::new(x as f64, y as f64, z as f64))
+                        .collect()
+                }),
+                // glTF images start at the top, ours at the bottom
+                uvs: reader.read_tex_coords(0).map(|uvs| {
+                    uvs.into_f32()
+                        .map(|[u, v]| (u as f64, 1.0 - v as f64))
+                        .collect()
+                }),
+                colors: reader.read_colors(0).map(|colors| {
+                    colors
+                        .into_rgb_f32()
+                        .map(|[r, g, b]| Vector3::new(r as f64, g as f64, b as f64))
+                        .collect()
+                }),
+                triangles: Vec::new(),
+            };
+            data.triangles = match reader.read_indices() {
+                Some(indices) => {
+                    let indices = indices.into_u32().map(|i| i as usize).collect::<Vec<_>>();
+                    indices
+                        .chunks_exact(3)
+                        .map(|t| [t[0], t[1], t[2]])
+                        .collect()
+                }
+                None => (0..data.positions.len() / 3)
+                    .map(|t| [t * 3, t * 3 + 1, t * 3 + 2])
+                    .collect(),
+            };
+
+            let material = self.material(&primitive.material())?;
+            let normal_map = match primitive.material().normal_texture() {
+                Some(normal) => {
+                    let image = &self.images[normal.texture().source().index()];
+                    let texture: TextureRef =
+                        Arc::new(ImageTexture::from_linear_image(to_rgb32f(image)?));
+                    Some(texture)
+                }
+                None => None,
+            };
+            let mesh: Arc<dyn Hittable> =
+                Arc::from(Mesh::new(data, material, normal_map) as HittableRef);
+            primitives.push(match self.opacity(&primitive.material())? {
+                Some((opacity, threshold)) => {
+                    Arc::from(AlphaMask::new(mesh, opacity, threshold) as HittableRef)
+                }
+                None => mesh,
+            });
+        }
+
+        self.meshes[mesh.index()] = Some(primitives.clone());
+        Ok(primitives)
+    }
+
+    /// Emissive materials become lights and the rest principled
+    fn material(&self, material: &gltf::Material) -> Result<MaterialRef, ConfigError> {
+        let emissive = material.emissive_factor();
+        if emissive.iter().any(|c| *c > 0.0) {
+            let [r, g, b] = emissive.map(|c| c as f64);
+            let factor = Vector3::new(r, g, b);
+            let emission: TextureRef = match material.emissive_texture() {
+                Some(info) => {
+                    let image = &self.images[info.texture().source().index()];
+                    Arc::new(ImageTexture::from_image(to_rgb32f(image)?).scaled(&factor))
+                }
+                None => Arc::new(SolidColor::new(factor)),
+            };
+            let strength = material.emissive_strength().unwrap_or(1.0) as f64;
+            return Ok(Arc::new(DiffuseLight::textured(
+                emission,
+                Vector3::fill(strength),
+                material.double_sided(),
+            )));
+        }
+
+        let pbr = material.pbr_metallic_roughness();
+        let [r, g, b, _] = pbr.base_color_factor().map(|c| c as f64);
+        let factor = Vector3::new(r, g, b);
+
+        let base_color: TextureRef = match pbr.base_color_texture() {
+            Some(info) => {
+                let image = &self.images[info.texture().source().index()];
+                Arc::new(ImageTexture::from_image(to_rgb32f(image)?).scaled(&factor))
+            }
+            None => Arc::new(SolidColor::new(factor)),
+        };
+
+        // roughness is in the green channel and metalness in the blue one
+        let metallic_roughness = pbr.metallic_roughness_texture();
+        let metallic = self.scalar_texture(
+            metallic_roughness.as_ref().map(|info| (info.texture(), 2)),
+            pbr.metallic_factor(),
+        )?;
+        let roughness = self.scalar_texture(
+            metallic_roughness.as_ref().map(|info| (info.texture(), 1)),
+            pbr.roughness_factor(),
+        )?;
+
+        let transmission = material.transmission();
+        let transmission = self.scalar_texture(
+            transmission
+                .as_ref()
+                .and_then(|t| t.transmission_texture())
+                .map(|info| (info.texture(), 0)),
+            transmission
+                .as_ref()
+                .map_or(0.0, |t| t.transmission_factor()),
+        )?;
+
+        let constant =
+            |value: f64| -> TextureRef { Arc::new(SolidColor::new(Vector3::fill(value))) };
+        Ok(Arc::new(Principled {
+            base_color,
+            metallic,
+            roughness,
+            specular: constant(0.5),
+            clearcoat: constant(0.0),
+            clearcoat_roughness: constant(0.03),
+            sheen: constant(0.0),
+            sheen_tint: constant(0.5),
+            sheen_lobe: Sheen::new(Vector3::fill(1.0), 0.5),
+            transmission,
+            refraction_index: material.ior().unwrap_or(1.5) as f64,
+        }))
+    }
+
+    /// Base color alpha for masked and blended materials, with the cutoff of masked ones
+    fn opacity(
+        &self,
+        material: &gltf::Material,
+    ) -> Result<Option<(TextureRef, Option<f64>)>, ConfigError> {
+        let threshold = match material.alpha_mode() {
+            AlphaMode::Opaque => return Ok(None),
+            AlphaMode::Mask => Some(material.alpha_cutoff().unwrap_or(0.5) as f64),
+            AlphaMode::Blend => None,
+        };
+        let pbr = material.pbr_metallic_roughness();
+        let factor = pbr.base_color_factor()[3];
+        let opacity = self.scalar_texture(
+            pbr.base_color_texture().map(|info| (info.texture(), 3)),
+            factor,
+        )?;
+        Ok(Some((opacity, threshold)))
+    }
+
+    /// One channel of a linear texture times `factor`, or just the factor
+    fn scalar_texture(
+        &self,
+        texture: Option<(gltf::Texture, usize)>,
+        factor: f32,
+    ) -> Result<TextureRef, ConfigError> {
+        let factor = Vector3::fill(factor as f64);
+        Ok(match texture {
+            Some((texture, channel)) => {
+                let image = channel_image(&self.images[texture.source().index()], channel)?;
+                Arc::new(ImageTexture::from_linear_image(image).scaled(&factor))
+            }
+            None => Arc::new(SolidColor::new(factor)),
+        })
+    }
+}
+
+fn to_rgb32f(image: &gltf::image::Data) -> Result<Rgb32FImage, ConfigError> {
+    let pixels = to_rgba(image)?
+        .into_iter()
+        .flat_map(|[r, g, b, _]| [r, g, b])
+        .collect();
+    Rgb32FImage::from_raw(image.width, image.height, pixels)
+        .ok_or_else(|| ConfigError::Message("glTF image has the wrong size".into()))
+}
+
+/// One channel repeated in all three, alpha being the fourth
+fn channel_image(image: &gltf::image::Data, channel: usize) -> Result<Rgb32FImage, ConfigError> {
+    let pixels = to_rgba(image)?
+        .into_iter()
+        .flat_map(|pixel| [pixel[channel]; 3])
+        .collect();
+    Rgb32FImage::from_raw(image.width, image.height, pixels)
+        .ok_or_else(|| ConfigError::Message("glTF image has the wrong size".into()))
+}
+
+fn to_rgba(image: &gltf::image::Data) -> Result<Vec<[f32; 4]>, ConfigError> {
+    let (channels, bytes) = match image.format {
+        Format::R8 => (1, 1),
+        Format::R8G8 => (2, 1),
+        Format::R8G8B8 => (3, 1),
+        Format::R8G8B8A8 => (4, 1),
+        Format::R16 => (1, 2),
+        Format::R16G16 => (2, 2),
+        Format::R16G16B16 => (3, 2),
+        Format::R16G16B16A16 => (4, 2),
+        format => {
+            return Err(ConfigError::Message(format!(
+                "unsupported glTF image format {format:?}"
+            )))
+        }
+    };
+
+    Ok(image
+        .pixels
+        .chunks_exact(channels * bytes)
+        .map(|pixel| {
+            let read = |c: usize| match bytes {
+                1 => pixel[c] as f64 / u8::MAX as f64,
+                _ => u16::from_le_bytes([pixel[2 * c], pixel[2 * c + 1]]) as f64 / u16::MAX as f64,
+            };
+            // grayscale images repeat their first channel, alpha is opaque without one
+            let color = |c: usize| read(if channels >= 3 { c } else { 0 }) as f32;
+            let alpha = if channels == 4 { read(3) as f32 } else { 1.0 };
+            [color(0), color(1), color(2), alpha]
+        })
+        .collect())
+}
@@ -0,0 +1,133 @@
+use std::f64::consts::PI;
+
+use config::ConfigError;
+use serde::Deserialize;
+
+use crate::{
+    light::{directional::DirectionalLight, point::PointLight, spot::SpotLight, LightRef},
+    math::Vector3,
+};
+
+/// Every light is `color` times the black body color at an optional `temperature`
+/// in Kelvin, times its strength
+#[derive(Debug, Deserialize)]
+pub(crate) struct LightDescription {
+    #[serde(default = "default_color")]
+    color: Vector3,
+    temperature: Option<f64>,
+    #[serde(flatten)]
+    kind: LightKind,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub(crate) enum LightKind {
+    /// Either an `intensity` in watts per steradian or the `power` in watts
+    Point {
+        position: Vector3,
+        intensity: Option<f64>,
+        power: Option<f64>,
+    },
+    /// Angles from the axis in degrees, the `power` is spread over the cone as lit by the
+    /// fade and the profile.
+    /// `profile` scales the intensity at evenly spaced angles up to the outer one.
+    Spot {
+        position: Vector3,
+        direction: Vector3,
+        #[serde(default)]
+        inner_angle: f64,
+        outer_angle: f64,
+        intensity: Option<f64>,
+        power: Option<f64>,
+        profile: Option<Vec<f64>>,
+    },
+    /// `direction` is the way the light travels, `irradiance` in watts per square meter
+    Directional {
+        direction: Vector3,
+        #[serde(default = "default_strength")]
+        irradiance: f64,
+    },
+}
+
+fn default_color() -> Vector3 {
+    Vector3::fill(1.0)
+}
+
+fn default_strength() -> f64 {
+    1.0
+}
+
+fn intensity(
+    name: &str,
+    intensity: Option<f64>,
+    power: Option<f64>,
+    solid_angle: f64,
+) -> Result<f64, ConfigError> {
+    match (intensity, power) {
+        (Some(_), Some(_)) => Err(ConfigError::Message(format!(
+            "{name} light takes either an intensity or a power"
+        ))),
+        (None, Some(power)) => Ok(power / solid_angle),
+        (intensity, None) => Ok(intensity.unwrap_or(1.0)),
+    }
+}
+
+fn check_direction(name: &str, direction: &Vector3) -> Result<(), ConfigError> {
+    if direction.near_zero() {
+        return Err(ConfigError::Message(format!(
+            "{name} light direction can't be zero"
+        )));
+    }
+    Ok(())
+}
+
+impl LightDescription {
+    pub(crate) fn build(self) -> Result<LightRef, ConfigError> {
+        let color = super::blackbody(self.temperature)? * self.color;
+
+        Ok(match self.kind {
+            LightKind::Point {
+                position,
+                intensity: strength,
+                power,
+            } => {
+                let strength = intensity("point", strength, power, 4.0 * PI)?;
+                Box::new(PointLight::new(position, color * strength))
+            }
+            LightKind::Spot {
+                position,
+                direction,
+                inner_angle,
+                outer_angle,
+                intensity: strength,
+                power,
+                profile,
+            } => {
+                if !(outer_angle > 0.0 && outer_angle <= 180.0) {
+                    return Err(ConfigError::Message(
+                        "spot light outer_angle must be within (0, 180] degrees".into(),
+                    ));
+                }
+                check_direction("spot", &direction)?;
+                let light = SpotLight::new(
+                    position,
+                    direction,
+                    color,
+                    inner_angle.to_radians(),
+                    outer_angle.to_radians(),
+                    profile,
+                );
+                // the power spreads over the cone as weighted by the fade and the profile
+                let strength = intensity("spot", strength, power, light.solid_angle())?;
+                Box::new(light.scaled(strength))
+            }
+            LightKind::Directional {
+                direction,
+                irradiance,
+            } => {
+                check_direction("directional", &direction)?;
+                Box::new(DirectionalLight::new(direction, color * irradiance))
+            }
+        })
+    }
+}
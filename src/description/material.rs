@@ -0,0 +1,296 @@
+use std::{f64::consts::PI, sync::Arc};
+
+use config::ConfigError;
+use serde::Deserialize;
+
+use crate::{
+    material::{
+        coated::Coated,
+        conductor::{Conductor, ConductorPreset},
+        diffuse_light::DiffuseLight,
+        glass::{Dispersion, Glass},
+        lambert::Lambert,
+        metal::Metal,
+        mix::Mix,
+        principled::Principled,
+        sheen::Sheen,
+        subsurface::Subsurface,
+        thin_film::ThinFilm,
+        MaterialRef,
+    },
+    math::Vector3,
+};
+
+use super::texture::{TextureDescription, TextureKind};
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub(crate) enum MaterialDescription {
+    /// Optional `sheen` lobe for cloth
+    Lambert {
+        albedo: TextureDescription,
+        sheen: Option<Sheen>,
+    },
+    Metal {
+        albedo: Vector3,
+        fuzz: f64,
+    },
+    /// Either a `preset` metal or its complex index of refraction `eta` and `k`
+    Conductor {
+        preset: Option<ConductorPreset>,
+        eta: Option<Vector3>,
+        k: Option<Vector3>,
+        #[serde(default)]
+        roughness: f64,
+        #[serde(default)]
+        anisotropy: f64,
+        thin_film: Option<ThinFilm>,
+    },
+    /// `absorption` per unit of length tints the interior, thicker parts get darker.
+    /// With `dispersion` the index defaults to the one at 550 nm.
+    Glass {
+        refraction_index: Option<f64>,
+        dispersion: Option<Dispersion>,
+        #[serde(default)]
+        roughness: f64,
+        absorption: Option<Vector3>,
+        thin_film: Option<ThinFilm>,
+    },
+    /// Emitted radiance is `color` (a color or texture) times `radiance`, or the `power`
+    /// in watts spread over the surface of a sphere, cuboid or mesh, times the black body
+    /// color at a `temperature` in Kelvin
+    DiffuseLight {
+        #[serde(default = "default_light_color")]
+        color: TextureDescription,
+        radiance: Option<f64>,
+        power: Option<f64>,
+        temperature: Option<f64>,
+        #[serde(default)]
+        two_sided: bool,
+    },
+    Principled(PrincipledDescription),
+    /// Scatters off `second` with the chance given by `weight`, a number or a texture
+    Mix {
+        first: Box<MaterialDescription>,
+        second: Box<MaterialDescription>,
+        weight: TextureDescription,
+    },
+    /// Random walk below a smooth dielectric boundary, `mean_free_path` per channel
+    Subsurface {
+        albedo: Vector3,
+        mean_free_path: Vector3,
+        #[serde(default = "default_subsurface_index")]
+        refraction_index: f64,
+        #[serde(default)]
+        anisotropy: f64,
+    },
+    /// Clear or tinted dielectric layer over the `base` material
+    Coated {
+        base: Box<MaterialDescription>,
+        #[serde(default = "default_coat_index")]
+        refraction_index: f64,
+        #[serde(default)]
+        roughness: f64,
+        #[serde(default = "default_coat_color")]
+        color: Vector3,
+    },
+}
+
+fn default_light_color() -> TextureDescription {
+    TextureDescription::Scalar(1.0)
+}
+
+fn default_subsurface_index() -> f64 {
+    1.4
+}
+
+fn default_coat_index() -> f64 {
+    1.5
+}
+
+fn default_coat_color() -> Vector3 {
+    Vector3::fill(1.0)
+}
+
+/// Every parameter is optional and takes a number, a color or a texture
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub(crate) struct PrincipledDescription {
+    base_color: TextureDescription,
+    metallic: TextureDescription,
+    roughness: TextureDescription,
+    specular: TextureDescription,
+    clearcoat: TextureDescription,
+    clearcoat_roughness: TextureDescription,
+    sheen: TextureDescription,
+    sheen_tint: TextureDescription,
+    sheen_roughness: f64,
+    transmission: TextureDescription,
+    refraction_index: f64,
+}
+
+impl Default for PrincipledDescription {
+    fn default() -> Self {
+        Self {
+            base_color: TextureDescription::Scalar(0.8),
+            metallic: TextureDescription::Scalar(0.0),
+            roughness: TextureDescription::Scalar(0.5),
+            specular: TextureDescription::Scalar(0.5),
+            clearcoat: TextureDescription::Scalar(0.0),
+            clearcoat_roughness: TextureDescription::Scalar(0.03),
+            sheen: TextureDescription::Scalar(0.0),
+            sheen_tint: TextureDescription::Scalar(0.5),
+            sheen_roughness: 0.5,
+            transmission: TextureDescription::Scalar(0.0),
+            refraction_index: 1.5,
+        }
+    }
+}
+
+impl PrincipledDescription {
+    fn build(self) -> Result<Principled, ConfigError> {
+        Ok(Principled {
+            base_color: self.base_color.build()?,
+            metallic: self.metallic.build()?,
+            roughness: self.roughness.build()?,
+            specular: self.specular.build()?,
+            clearcoat: self.clearcoat.build()?,
+            clearcoat_roughness: self.clearcoat_roughness.build()?,
+            sheen: self.sheen.build()?,
+            sheen_tint: self.sheen_tint.build()?,
+            sheen_lobe: Sheen::new(Vector3::fill(1.0), self.sheen_roughness),
+            transmission: self.transmission.build()?,
+            refraction_index: self.refraction_index,
+        })
+    }
+}
+
+impl Default for MaterialDescription {
+    fn default() -> Self {
+        Self::Lambert {
+            albedo: TextureDescription::Color(Vector3::fill(0.5)),
+            sheen: None,
+        }
+    }
+}
+
+impl MaterialDescription {
+    /// Grey unless the mesh has vertex colors
+    pub(crate) fn vertex_colored() -> Self {
+        Self::Lambert {
+            albedo: TextureDescription::Texture(TextureKind::VertexColor {
+                fallback: Vector3::fill(0.5),
+            }),
+            sheen: None,
+        }
+    }
+
+    pub(crate) fn build(self) -> Result<MaterialRef, ConfigError> {
+        self.build_with_area(None)
+    }
+
+    /// `area` of the surface the material covers, needed by lights given in watts
+    pub(crate) fn build_with_area(self, area: Option<f64>) -> Result<MaterialRef, ConfigError> {
+        Ok(match self {
+            Self::Lambert { albedo, sheen } => {
+                Arc::new(Lambert::with_sheen(albedo.build()?, sheen))
+            }
+            Self::Metal { albedo, fuzz } => Arc::new(Metal::new(albedo, fuzz)),
+            Self::Conductor {
+                preset,
+                eta,
+                k,
+                roughness,
+                anisotropy,
+                thin_film,
+            } => {
+                let (eta, k) = match (preset, eta, k) {
+                    (Some(preset), None, None) => preset.ior(),
+                    (None, Some(eta), Some(k)) => (eta, k),
+                    _ => {
+                        return Err(ConfigError::Message(
+                            "conductor needs either a preset or both eta and k".into(),
+                        ))
+                    }
+                };
+                Arc::new(Conductor::new(eta, k, roughness, anisotropy, thin_film))
+            }
+            Self::Glass {
+                refraction_index,
+                dispersion,
+                roughness,
+                absorption,
+                thin_film,
+            } => {
+                let refraction_index = refraction_index
+                    .or(dispersion.as_ref().map(|d| d.index(550.0)))
+                    .ok_or(ConfigError::Message(
+                        "glass needs a refraction_index or a dispersion".into(),
+                    ))?;
+                Arc::new(Glass::with_parameters(
+                    refraction_index,
+                    roughness,
+                    absorption.unwrap_or(Vector3::fill(0.0)),
+                    dispersion,
+                    thin_film,
+                ))
+            }
+            Self::DiffuseLight {
+                color,
+                radiance,
+                power,
+                temperature,
+                two_sided,
+            } => {
+                let scale = match (radiance, power, area) {
+                    (Some(_), Some(_), _) => {
+                        return Err(ConfigError::Message(
+                            "diffuse_light takes either a radiance or a power".into(),
+                        ))
+                    }
+                    (None, Some(_), None) => {
+                        return Err(ConfigError::Message(
+                            "diffuse_light power needs a sphere, cuboid or mesh".into(),
+                        ))
+                    }
+                    // a lambertian emitter sends out pi times its radiance per unit of area
+                    (None, Some(power), Some(area)) => {
+                        let sides = if two_sided { 2.0 } else { 1.0 };
+                        power / (PI * area * sides)
+                    }
+                    (radiance, None, _) => radiance.unwrap_or(1.0),
+                };
+                let intensity = super::blackbody(temperature)? * scale;
+                Arc::new(DiffuseLight::textured(color.build()?, intensity, two_sided))
+            }
+            Self::Principled(description) => Arc::new(description.build()?),
+            Self::Mix {
+                first,
+                second,
+                weight,
+            } => Arc::new(Mix::new(first.build()?, second.build()?, weight.build()?)),
+            Self::Subsurface {
+                albedo,
+                mean_free_path,
+                refraction_index,
+                anisotropy,
+            } => Arc::new(Subsurface::new(
+                albedo,
+                mean_free_path,
+                refraction_index,
+                anisotropy,
+            )),
+            Self::Coated {
+                base,
+                refraction_index,
+                roughness,
+                color,
+            } => Arc::new(Coated::new(
+                base.build()?,
+                refraction_index,
+                roughness,
+                color,
+            )),
+        })
+    }
+}
@@ -0,0 +1,55 @@
+use config::{Config, ConfigError, File};
+use serde::Deserialize;
+
+use crate::{geometry::HittableRef, light::LightRef, math::Vector3, spectrum};
+
+pub mod background;
+pub mod gltf;
+pub mod light;
+pub mod material;
+pub mod object;
+pub mod texture;
+
+/// Coolest black body accepted, below it the color is too dim to normalize
+const MIN_TEMPERATURE: f64 = 500.0;
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct SceneDescription {
+    #[serde(default)]
+    pub(crate) background: background::BackgroundDescription,
+    #[serde(default)]
+    pub(crate) objects: Vec<object::ObjectDescription>,
+    #[serde(default)]
+    pub(crate) lights: Vec<light::LightDescription>,
+}
+
+impl SceneDescription {
+    pub(crate) fn load(input_file: &str) -> Result<Self, ConfigError> {
+        let s = Config::builder()
+            .add_source(File::with_name(input_file))
+            .build()?;
+
+        s.try_deserialize()
+    }
+
+    pub(crate) fn build_objects(&mut self) -> Result<Vec<HittableRef>, ConfigError> {
+        self.objects.drain(..).map(|o| o.build()).collect()
+    }
+
+    pub(crate) fn build_lights(&mut self) -> Result<Vec<LightRef>, ConfigError> {
+        self.lights.drain(..).map(|l| l.build()).collect()
+    }
+}
+
+/// Black body color at an optional `temperature` in Kelvin, white without one
+pub(crate) fn blackbody(temperature: Option<f64>) -> Result<Vector3, ConfigError> {
+    match temperature {
+        None => Ok(Vector3::fill(1.0)),
+        Some(temperature) if temperature >= MIN_TEMPERATURE && temperature.is_finite() => {
+            Ok(spectrum::blackbody(temperature))
+        }
+        Some(temperature) => Err(ConfigError::Message(format!(
+            "temperature must be at least {MIN_TEMPERATURE} K, got {temperature}"
+        ))),
+    }
+}
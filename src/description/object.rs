@@ -0,0 +1,355 @@
+use std::{f64::consts::PI, sync::Arc};
+
+use config::ConfigError;
+use serde::Deserialize;
+
+use crate::{
+    geometry::{
+        alpha_mask::AlphaMask,
+        constant_medium::ConstantMedium,
+        csg::{Csg, Operation},
+        cuboid::Cuboid,
+        grid_medium::GridMedium,
+        heightfield::Heightfield,
+        list::HittableList,
+        mesh::{ply, stl, Mesh, MeshData},
+        sdf::{Sdf, SdfNode},
+        sphere::Sphere,
+        HittableRef,
+    },
+    material::henyey_greenstein::HenyeyGreenstein,
+    math::Vector3,
+    texture::{image_texture::ImageTexture, TextureRef},
+};
+
+use super::{gltf, material::MaterialDescription, texture::TextureDescription};
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub(crate) enum ObjectDescription {
+    Sphere {
+        center: Vector3,
+        radius: f64,
+        #[serde(default)]
+        material: MaterialDescription,
+    },
+    Cuboid {
+        min: Vector3,
+        max: Vector3,
+        #[serde(default)]
+        material: MaterialDescription,
+    },
+    Csg {
+        operation: Operation,
+        left: Box<ObjectDescription>,
+        right: Box<ObjectDescription>,
+    },
+    Sdf {
+        shape: SdfNode,
+        #[serde(default = "default_max_distance")]
+        max_distance: f64,
+        #[serde(default = "default_step_scale")]
+        step_scale: f64,
+        #[serde(default)]
+        material: MaterialDescription,
+    },
+    Heightfield {
+        file: String,
+        corner: Vector3,
+        extent: [f64; 2],
+        height_scale: f64,
+        #[serde(default)]
+        material: MaterialDescription,
+    },
+    /// PLY or STL file, by extension
+    Mesh {
+        file: String,
+        #[serde(default = "default_scale")]
+        scale: f64,
+        #[serde(default = "no_offset")]
+        offset: Vector3,
+        #[serde(default = "MaterialDescription::vertex_colored")]
+        material: MaterialDescription,
+        /// Average the face normals when the file has none
+        #[serde(default)]
+        smooth: bool,
+        normal_map: Option<String>,
+    },
+    /// Every mesh in a glTF file, cameras are ignored
+    Gltf { file: String },
+    /// Cuts `object` out where `opacity` is low, stochastically unless a `threshold` is set
+    AlphaMask {
+        object: Box<ObjectDescription>,
+        opacity: TextureDescription,
+        threshold: Option<f64>,
+    },
+    ConstantMedium {
+        boundary: Box<ObjectDescription>,
+        density: f64,
+        albedo: Vector3,
+        #[serde(default)]
+        anisotropy: f64,
+    },
+    GridMedium {
+        file: String,
+        resolution: [usize; 3],
+        min: Vector3,
+        max: Vector3,
+        absorption: f64,
+        scattering: f64,
+        #[serde(default = "no_emission")]
+        emission: Vector3,
+        #[serde(default)]
+        anisotropy: f64,
+    },
+}
+
+fn default_max_distance() -> f64 {
+    100.0
+}
+
+fn default_step_scale() -> f64 {
+    1.0
+}
+
+fn default_scale() -> f64 {
+    1.0
+}
+
+fn no_offset() -> Vector3 {
+    Vector3::fill(0.0)
+}
+
+fn no_emission() -> Vector3 {
+    Vector3::fill(0.0)
+}
+
+impl ObjectDescription {
+    /// Objects enclosing a volume, that can be combined with CSG
+    fn is_solid(&self) -> bool {
+        matches!(
+            self,
+            Self::Sphere { .. } | Self::Cuboid { .. } | Self::Csg { .. }
+        )
+    }
+
+    pub(crate) fn build(self) -> Result<HittableRef, ConfigError> {
+        Ok(match self {
+            Self::Sphere {
+                center,
+                radius,
+                material,
+            } => {
+                let area = 4.0 * PI * radius * radius;
+                Sphere::with_material(center, radius, material.build_with_area(Some(area))?)
+            }
+            Self::Cuboid { min, max, material } => {
+                let size = &max - &min;
+                let area = 2.0 * (size.x * size.y + size.y * size.z + size.z * size.x).abs();
+                Cuboid::with_material(min, max, material.build_with_area(Some(area))?)
+            }
+            Self::Csg {
+                operation,
+                left,
+                right,
+            } => {
+                if !left.is_solid() || !right.is_solid() {
+                    return Err(ConfigError::Message(
+                        "csg operands must be spheres, cuboids or other csg objects".into(),
+                    ));
+                }
+                Csg::new(operation, left.build()?, right.build()?)
+            }
+            Self::Sdf {
+                shape,
+                max_distance,
+                step_scale,
+                material,
+            } => {
+                if step_scale <= 0.0 || step_scale > 1.0 {
+                    return Err(ConfigError::Message(format!(
+                        "sdf step_scale must be in (0, 1], got {step_scale}"
+                    )));
+                }
+                if max_distance <= 0.0 {
+                    return Err(ConfigError::Message(format!(
+                        "sdf max_distance must be positive, got {max_distance}"
+                    )));
+                }
+                Sdf::new(shape, max_distance, step_scale, material.build()?)
+            }
+            Self::Heightfield {
+                file,
+                corner,
+                extent,
+                height_scale,
+                material,
+            } => {
+                if extent.iter().any(|e| *e <= 0.0) {
+                    return Err(ConfigError::Message(format!(
+                        "heightfield extent must be positive, got {extent:?}"
+                    )));
+                }
+                let (samples, columns, rows) = load_heightmap(&file)?;
+                Heightfield::new(
+                    samples,
+                    columns,
+                    rows,
+                    corner,
+                    extent,
+                    height_scale,
+                    material.build()?,
+                )
+            }
+            Self::Mesh {
+                file,
+                scale,
+                offset,
+                material,
+                smooth,
+                normal_map,
+            } => {
+                let mut data = load_mesh(&file)?;
+                data.transform(scale, &offset);
+                if smooth && data.normals.is_none() {
+                    data.compute_normals();
+                }
+                let normal_map = match normal_map {
+                    Some(file) => {
+                        let texture: TextureRef =
+                            Arc::new(ImageTexture::open_linear(&file).map_err(|e| {
+                                ConfigError::Message(format!(
+                                    "failed to load normal map {file}: {e}"
+                                ))
+                            })?);
+                        Some(texture)
+                    }
+                    None => None,
+                };
+                let material = material.build_with_area(Some(data.area()))?;
+                Mesh::new(data, material, normal_map)
+            }
+            Self::Gltf { file } => HittableList::new(gltf::load(&file)?.objects),
+            Self::AlphaMask {
+                object,
+                opacity,
+                threshold,
+            } => AlphaMask::new(Arc::from(object.build()?), opacity.build()?, threshold),
+            Self::ConstantMedium {
+                boundary,
+                density,
+                albedo,
+                anisotropy,
+            } => {
+                if density <= 0.0 {
+                    return Err(ConfigError::Message(format!(
+                        "constant medium density must be positive, got {density}"
+                    )));
+                }
+                if anisotropy == 0.0 {
+                    ConstantMedium::new(boundary.build()?, density, albedo)
+                } else {
+                    let phase_function = Arc::new(HenyeyGreenstein::new(albedo, anisotropy));
+                    ConstantMedium::with_phase_function(boundary.build()?, density, phase_function)
+                }
+            }
+            Self::GridMedium {
+                file,
+                resolution,
+                min,
+                max,
+                absorption,
+                scattering,
+                emission,
+                anisotropy,
+            } => {
+                if min.x >= max.x || min.y >= max.y || min.z >= max.z {
+                    return Err(ConfigError::Message(format!(
+                        "grid medium min must be below max on every axis, got {min:?} and {max:?}"
+                    )));
+                }
+                if absorption < 0.0 || scattering < 0.0 {
+                    return Err(ConfigError::Message(format!(
+                        "grid medium absorption and scattering can't be negative, got {absorption} and {scattering}"
+                    )));
+                }
+                GridMedium::new(
+                    min,
+                    max,
+                    resolution,
+                    load_density_grid(&file, resolution)?,
+                    absorption,
+                    scattering,
+                    emission,
+                    anisotropy,
+                )
+            }
+        })
+    }
+}
+
+/// Raw little endian `f32` densities, x varying fastest, then y, then z
+fn load_density_grid(file: &str, resolution: [usize; 3]) -> Result<Vec<f32>, ConfigError> {
+    let bytes = std::fs::read(file)
+        .map_err(|e| ConfigError::Message(format!("failed to read density grid {file}: {e}")))?;
+
+    let expected = resolution.iter().product::<usize>();
+    if expected == 0 || bytes.len() != expected * 4 {
+        return Err(ConfigError::Message(format!(
+            "density grid {file} has {} bytes, expected {} for resolution {resolution:?}",
+            bytes.len(),
+            expected * 4
+        )));
+    }
+
+    let density: Vec<f32> = bytes
+        .chunks_exact(4)
+        .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .collect();
+    if let Some(value) = density.iter().find(|d| !(**d >= 0.0 && d.is_finite())) {
+        return Err(ConfigError::Message(format!(
+            "density grid {file} has a negative or invalid density {value}"
+        )));
+    }
+    Ok(density)
+}
+
+/// Grayscale samples in `0..=1` and the image dimensions
+fn load_heightmap(file: &str) -> Result<(Vec<f64>, usize, usize), ConfigError> {
+    let image = image::open(file)
+        .map_err(|e| ConfigError::Message(format!("failed to load heightmap {file}: {e}")))?
+        .into_luma16();
+
+    let (columns, rows) = (image.width() as usize, image.height() as usize);
+    if columns < 2 || rows < 2 {
+        return Err(ConfigError::Message(format!(
+            "heightmap {file} must be at least 2x2 pixels, got {columns}x{rows}"
+        )));
+    }
+
+    let samples = image
+        .pixels()
+        .map(|p| p.0[0] as f64 / u16::MAX as f64)
+        .collect();
+    Ok((samples, columns, rows))
+}
+
+fn load_mesh(file: &str) -> Result<MeshData, ConfigError> {
+    let extension = std::path::Path::new(file)
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(str::to_lowercase);
+    let parse = match extension.as_deref() {
+        Some("ply") => ply::parse,
+        Some("stl") => stl::parse,
+        _ => {
+            return Err(ConfigError::Message(format!(
+                "unsupported mesh format for {file}, expected .ply or .stl"
+            )))
+        }
+    };
+
+    let bytes = std::fs::read(file)
+        .map_err(|e| ConfigError::Message(format!("failed to read mesh {file}: {e}")))?;
+    parse(&bytes).map_err(|e| ConfigError::Message(format!("failed to load mesh {file}: {e}")))
+}
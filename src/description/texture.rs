@@ -0,0 +1,68 @@
+use std::sync::Arc;
+
+use config::ConfigError;
+use serde::Deserialize;
+
+use crate::{
+    math::Vector3,
+    texture::{
+        image_texture::ImageTexture, solid_color::SolidColor, vertex_color::VertexColor, TextureRef,
+    },
+};
+
+/// Either a plain `[r, g, b]` color, a number for all three channels or a texture table with a `type`
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub(crate) enum TextureDescription {
+    Color(Vector3),
+    Scalar(f64),
+    Texture(TextureKind),
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub(crate) enum TextureKind {
+    /// `linear` skips the gamma decoding, for data like roughness maps
+    Image {
+        file: String,
+        #[serde(default)]
+        linear: bool,
+    },
+    VertexColor {
+        #[serde(default = "default_fallback")]
+        fallback: Vector3,
+    },
+    /// Alpha channel of an image, for opacity masks
+    Alpha { file: String },
+}
+
+fn default_fallback() -> Vector3 {
+    Vector3::fill(0.5)
+}
+
+impl TextureDescription {
+    pub(crate) fn build(self) -> Result<TextureRef, ConfigError> {
+        Ok(match self {
+            Self::Color(color) => Arc::new(SolidColor::new(color)),
+            Self::Scalar(value) => Arc::new(SolidColor::new(Vector3::fill(value))),
+            Self::Texture(TextureKind::Image { file, linear }) => {
+                let texture = if linear {
+                    ImageTexture::open_linear(&file)
+                } else {
+                    ImageTexture::open(&file)
+                };
+                Arc::new(texture.map_err(|e| {
+                    ConfigError::Message(format!("failed to load texture {file}: {e}"))
+                })?)
+            }
+            Self::Texture(TextureKind::Alpha { file }) => {
+                Arc::new(ImageTexture::open_alpha(&file).map_err(|e| {
+                    ConfigError::Message(format!("failed to load texture {file}: {e}"))
+                })?)
+            }
+            Self::Texture(TextureKind::VertexColor { fallback }) => {
+                Arc::new(VertexColor::new(fallback))
+            }
+        })
+    }
+}
@@ -0,0 +1,66 @@
+use crate::{
+    component::ray::Ray,
+    math::{Interval, Vector3},
+};
+
+/// Axis aligned bounding box
+#[derive(Clone, Debug)]
+pub struct Aabb {
+    pub min: Vector3,
+    pub max: Vector3,
+}
+
+impl Aabb {
+    pub fn new(min: Vector3, max: Vector3) -> Self {
+        Self { min, max }
+    }
+
+    pub fn empty() -> Self {
+        Self::new(
+            Vector3::fill(f64::INFINITY),
+            Vector3::fill(f64::NEG_INFINITY),
+        )
+    }
+
+    pub fn grow(&mut self, point: &Vector3) {
+        self.min = Vector3::new(
+            self.min.x.min(point.x),
+            self.min.y.min(point.y),
+            self.min.z.min(point.z),
+        );
+        self.max = Vector3::new(
+            self.max.x.max(point.x),
+            self.max.y.max(point.y),
+            self.max.z.max(point.z),
+        );
+    }
+
+    pub fn centroid(&self) -> Vector3 {
+        (&self.min + &self.max) / 2.0
+    }
+
+    /// Parametric range of the ray inside the box, clipped to the interval
+    pub fn hit(&self, ray: &Ray, interval: Interval) -> Option<Interval> {
+        let origin = [ray.origin.x, ray.origin.y, ray.origin.z];
+        let direction = [ray.direction.x, ray.direction.y, ray.direction.z];
+        let min = [self.min.x, self.min.y, self.min.z];
+        let max = [self.max.x, self.max.y, self.max.z];
+
+        let mut inside = interval;
+        for axis in 0..3 {
+            let inv = 1.0 / direction[axis];
+            let mut near = (min[axis] - origin[axis]) * inv;
+            let mut far = (max[axis] - origin[axis]) * inv;
+            if near > far {
+                std::mem::swap(&mut near, &mut far);
+            }
+            inside.min = inside.min.max(near);
+            inside.max = inside.max.min(far);
+            // flat boxes, like the bounds of a plane, still have a single point
+            if inside.min > inside.max {
+                return None;
+            }
+        }
+        Some(inside)
+    }
+}
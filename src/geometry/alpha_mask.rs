@@ -0,0 +1,72 @@
+use std::sync::Arc;
+
+use crate::{
+    component::{
+        hit::{Hit, HitRecord},
+        ray::Ray,
+    },
+    material::MaterialRef,
+    math::{self, Interval},
+    texture::TextureRef,
+};
+
+use super::Hittable;
+
+/// Cutout of another object where its `opacity` (first channel) is low, for foliage and
+/// decals. Rejected hits let the ray carry on as if nothing were there.
+pub struct AlphaMask {
+    object: Arc<dyn Hittable>,
+    opacity: TextureRef,
+    /// Opaque at or above it, otherwise kept with the chance given by the opacity
+    threshold: Option<f64>,
+}
+
+impl AlphaMask {
+    pub fn new(
+        object: Arc<dyn Hittable>,
+        opacity: TextureRef,
+        threshold: Option<f64>,
+    ) -> Box<Self> {
+        Box::new(Self {
+            object,
+            opacity,
+            threshold,
+        })
+    }
+
+    /// Whether the point is kept, randomly so for blended opacity
+    fn opaque(&self, record: &HitRecord) -> bool {
+        let opacity = self.opacity.value(record).x;
+        match self.threshold {
+            Some(threshold) => opacity >= threshold,
+            None => math::rand_f64() < opacity,
+        }
+    }
+}
+
+impl Hittable for AlphaMask {
+    fn hit(&self, ray: &Ray, interval: Interval) -> Option<Hit> {
+        let mut interval = interval;
+        loop {
+            let hit = self.object.hit(ray, interval)?;
+            if self.opaque(&hit.record) {
+                return Some(hit);
+            }
+            interval.min = hit.record.t;
+        }
+    }
+
+    fn emitting_area(&self) -> f64 {
+        self.object.emitting_area()
+    }
+
+    /// `None` where the point is cut out, so the holes emit nothing
+    fn sample_emitting(&self) -> Option<Hit> {
+        let hit = self.object.sample_emitting()?;
+        self.opaque(&hit.record).then_some(hit)
+    }
+
+    fn for_each_material(&self, found: &mut dyn FnMut(&MaterialRef)) {
+        self.object.for_each_material(found);
+    }
+}
@@ -0,0 +1,85 @@
+use std::sync::Arc;
+
+use crate::{
+    component::{
+        hit::{Hit, HitRecord},
+        ray::Ray,
+    },
+    material::{isotropic::Isotropic, MaterialRef},
+    math::{self, Interval, Vector3},
+};
+
+use super::{Hittable, HittableRef};
+
+pub struct ConstantMedium {
+    boundary: HittableRef,
+    neg_inv_density: f64,
+    phase_function: MaterialRef,
+}
+
+impl ConstantMedium {
+    pub fn new(boundary: HittableRef, density: f64, albedo: Vector3) -> Box<Self> {
+        Self::with_phase_function(boundary, density, Arc::new(Isotropic::new(albedo)))
+    }
+
+    pub fn with_phase_function(
+        boundary: HittableRef,
+        density: f64,
+        phase_function: MaterialRef,
+    ) -> Box<Self> {
+        Box::new(Self {
+            boundary,
+            neg_inv_density: -1.0 / density,
+            phase_function,
+        })
+    }
+}
+
+impl Hittable for ConstantMedium {
+    fn hit(&self, ray: &Ray, interval: Interval) -> Option<Hit> {
+        // Entry and exit points of the boundary along the whole ray line
+        let enter = self.boundary.hit(ray, Interval::universe())?;
+        let exit = self
+            .boundary
+            .hit(ray, Interval::new(enter.record.t + 0.0001, f64::INFINITY))?;
+
+        let t_enter = enter.record.t.max(interval.min).max(0.0);
+        let t_exit = exit.record.t.min(interval.max);
+        if t_enter >= t_exit {
+            return None;
+        }
+
+        let ray_length = ray.direction.len();
+        let distance_inside = (t_exit - t_enter) * ray_length;
+        let hit_distance = self.neg_inv_density * math::rand_f64().ln();
+        if hit_distance > distance_inside {
+            return None;
+        }
+
+        let t = t_enter + hit_distance / ray_length;
+        let record = HitRecord {
+            point: ray.at(t),
+            // arbitrary, the phase function does not depend on it
+            normal: Vector3::new(1.0, 0.0, 0.0),
+            shading_normal: Vector3::new(1.0, 0.0, 0.0),
+            tangent: None,
+            direction: ray.direction.clone(),
+            t,
+            front: true,
+            u: 0.0,
+            v: 0.0,
+            vertex_color: None,
+            barycentrics: None,
+            wavelengths: None,
+        };
+        Some(Hit {
+            record,
+            material: self.phase_function.clone(),
+        })
+    }
+
+    fn for_each_material(&self, found: &mut dyn FnMut(&MaterialRef)) {
+        self.boundary.for_each_material(found);
+        found(&self.phase_function);
+    }
+}
@@ -0,0 +1,94 @@
+use serde::Deserialize;
+
+use crate::{
+    component::{hit::Hit, ray::Ray},
+    material::MaterialRef,
+    math::Interval,
+};
+
+use super::{first_boundary, Hittable, HittableRef, Span};
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Operation {
+    Union,
+    Intersection,
+    Difference,
+}
+
+impl Operation {
+    fn inside(self, left: bool, right: bool) -> bool {
+        match self {
+            Self::Union => left || right,
+            Self::Intersection => left && right,
+            Self::Difference => left && !right,
+        }
+    }
+}
+
+/// Constructive solid geometry node, combining the spans of two solids
+pub struct Csg {
+    operation: Operation,
+    left: HittableRef,
+    right: HittableRef,
+}
+
+impl Csg {
+    pub fn new(operation: Operation, left: HittableRef, right: HittableRef) -> Box<Self> {
+        Box::new(Self {
+            operation,
+            left,
+            right,
+        })
+    }
+}
+
+impl Hittable for Csg {
+    fn hit(&self, ray: &Ray, interval: Interval) -> Option<Hit> {
+        first_boundary(self.spans(ray), interval)
+    }
+
+    fn spans(&self, ray: &Ray) -> Vec<Span> {
+        // (t, from left operand, entering operand, boundary)
+        let mut boundaries = Vec::new();
+        for (is_left, spans) in [(true, self.left.spans(ray)), (false, self.right.spans(ray))] {
+            for span in spans {
+                boundaries.push((span.enter.record.t, is_left, true, span.enter));
+                boundaries.push((span.exit.record.t, is_left, false, span.exit));
+            }
+        }
+        boundaries.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        let (mut in_left, mut in_right) = (false, false);
+        let mut enter = None;
+        let mut spans = Vec::new();
+
+        for (_, is_left, entering, mut hit) in boundaries {
+            let was_inside = self.operation.inside(in_left, in_right);
+            if is_left {
+                in_left = entering;
+            } else {
+                in_right = entering;
+            }
+            let is_inside = self.operation.inside(in_left, in_right);
+            if was_inside == is_inside {
+                continue;
+            }
+
+            // Normals always face against the ray, only the side changes
+            hit.record.front = is_inside;
+            if is_inside {
+                enter = Some(hit);
+            } else if let Some(enter) = enter.take() {
+                spans.push(Span { enter, exit: hit });
+            }
+        }
+
+        spans
+    }
+
+    fn for_each_material(&self, found: &mut dyn FnMut(&MaterialRef)) {
+        self.left.for_each_material(found);
+        self.right.for_each_material(found);
+    }
+}
@@ -0,0 +1,168 @@
+use crate::{
+    component::{
+        hit::{Hit, HitRecord},
+        ray::Ray,
+    },
+    material::MaterialRef,
+    math::{self, Interval, Vector3},
+};
+
+use super::{first_boundary, Hittable, Span};
+
+/// Axis aligned box between two opposite corners
+pub struct Cuboid {
+    min: Vector3,
+    max: Vector3,
+    material: MaterialRef,
+}
+
+impl Cuboid {
+    pub fn with_material(a: Vector3, b: Vector3, material: MaterialRef) -> Box<Self> {
+        Box::new(Self {
+            min: Vector3::new(a.x.min(b.x), a.y.min(b.y), a.z.min(b.z)),
+            max: Vector3::new(a.x.max(b.x), a.y.max(b.y), a.z.max(b.z)),
+            material,
+        })
+    }
+
+    /// Area of one face across each axis
+    fn face_areas(&self) -> [f64; 3] {
+        let size = &self.max - &self.min;
+        [size.y * size.z, size.x * size.z, size.x * size.y]
+    }
+
+    fn hit_at(&self, ray: &Ray, t: f64, axis: usize, front: bool) -> Hit {
+        let mut normal = Vector3::fill(0.0);
+        let direction = [ray.direction.x, ray.direction.y, ray.direction.z][axis];
+        // facing against the ray, like every other record
+        let facing = if direction > 0.0 { -1.0 } else { 1.0 };
+        match axis {
+            0 => normal.x = facing,
+            1 => normal.y = facing,
+            _ => normal.z = facing,
+        }
+
+        // face coordinates from the two other axes
+        let point = ray.at(t);
+        let local = (&point - &self.min) / (&self.max - &self.min);
+        let (u, v) = match axis {
+            0 => (local.z, local.y),
+            1 => (local.x, local.z),
+            _ => (local.x, local.y),
+        };
+        let tangent = match axis {
+            0 => Vector3::new(0.0, 0.0, 1.0),
+            _ => Vector3::new(1.0, 0.0, 0.0),
+        };
+
+        let record = HitRecord {
+            point,
+            shading_normal: normal.clone(),
+            tangent: Some(tangent),
+            normal,
+            direction: ray.direction.clone(),
+            t,
+            front,
+            u,
+            v,
+            vertex_color: None,
+            barycentrics: None,
+            wavelengths: None,
+        };
+        Hit {
+            record,
+            material: self.material.clone(),
+        }
+    }
+}
+
+impl Hittable for Cuboid {
+    fn hit(&self, ray: &Ray, interval: Interval) -> Option<Hit> {
+        first_boundary(self.spans(ray), interval)
+    }
+
+    fn spans(&self, ray: &Ray) -> Vec<Span> {
+        let origin = [ray.origin.x, ray.origin.y, ray.origin.z];
+        let direction = [ray.direction.x, ray.direction.y, ray.direction.z];
+        let min = [self.min.x, self.min.y, self.min.z];
+        let max = [self.max.x, self.max.y, self.max.z];
+
+        let mut inside = Interval::universe();
+        let (mut enter_axis, mut exit_axis) = (0, 0);
+        for axis in 0..3 {
+            let inv = 1.0 / direction[axis];
+            let mut near = (min[axis] - origin[axis]) * inv;
+            let mut far = (max[axis] - origin[axis]) * inv;
+            if near > far {
+                std::mem::swap(&mut near, &mut far);
+            }
+            if near > inside.min {
+                inside.min = near;
+                enter_axis = axis;
+            }
+            if far < inside.max {
+                inside.max = far;
+                exit_axis = axis;
+            }
+            if inside.is_empty() {
+                return Vec::new();
+            }
+        }
+
+        vec![Span {
+            enter: self.hit_at(ray, inside.min, enter_axis, true),
+            exit: self.hit_at(ray, inside.max, exit_axis, false),
+        }]
+    }
+
+    fn emitting_area(&self) -> f64 {
+        if !self.material.emissive() {
+            return 0.0;
+        }
+        self.face_areas().iter().sum::<f64>() * 2.0
+    }
+
+    fn sample_emitting(&self) -> Option<Hit> {
+        if !self.material.emissive() {
+            return None;
+        }
+        // a face in proportion to its area, then a point on it
+        let areas = self.face_areas();
+        let mut pick = math::rand_f64() * areas.iter().sum::<f64>();
+        let axis = areas
+            .iter()
+            .position(|area| {
+                pick -= area;
+                pick < 0.0
+            })
+            .unwrap_or(2);
+
+        let mut point = Vector3::new(
+            math::rand_range_f64(self.min.x, self.max.x),
+            math::rand_range_f64(self.min.y, self.max.y),
+            math::rand_range_f64(self.min.z, self.max.z),
+        );
+        let mut outward = Vector3::fill(0.0);
+        let high = math::rand_f64() < 0.5;
+        match (axis, high) {
+            (0, _) => {
+                point.x = if high { self.max.x } else { self.min.x };
+                outward.x = if high { 1.0 } else { -1.0 };
+            }
+            (1, _) => {
+                point.y = if high { self.max.y } else { self.min.y };
+                outward.y = if high { 1.0 } else { -1.0 };
+            }
+            _ => {
+                point.z = if high { self.max.z } else { self.min.z };
+                outward.z = if high { 1.0 } else { -1.0 };
+            }
+        }
+        let ray = Ray::new(&point + &outward, -outward);
+        Some(self.hit_at(&ray, 1.0, axis, true))
+    }
+
+    fn for_each_material(&self, found: &mut dyn FnMut(&MaterialRef)) {
+        found(&self.material);
+    }
+}
@@ -0,0 +1,170 @@
+use std::sync::Arc;
+
+use crate::{
+    component::{
+        hit::{Hit, HitRecord},
+        ray::Ray,
+    },
+    material::{diffuse_light::DiffuseLight, henyey_greenstein::HenyeyGreenstein, MaterialRef},
+    math::{self, Interval, Vector3},
+};
+
+use super::{aabb::Aabb, Hittable, T_EPSILON};
+
+/// Voxel grid of densities inside an axis aligned box, sampled with delta tracking and
+/// seen through with ratio tracking
+pub struct GridMedium {
+    bounds: Aabb,
+    resolution: [usize; 3],
+    density: Vec<f32>,
+    absorption: f64,
+    scattering: f64,
+    majorant: f64,
+    phase_function: MaterialRef,
+    emitter: MaterialRef,
+}
+
+impl GridMedium {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        min: Vector3,
+        max: Vector3,
+        resolution: [usize; 3],
+        density: Vec<f32>,
+        absorption: f64,
+        scattering: f64,
+        emission: Vector3,
+        anisotropy: f64,
+    ) -> Box<Self> {
+        let max_density = density.iter().copied().fold(0.0_f32, f32::max) as f64;
+
+        Box::new(Self {
+            bounds: Aabb::new(min, max),
+            resolution,
+            density,
+            absorption,
+            scattering,
+            majorant: max_density * (absorption + scattering),
+            // the event probabilities already account for the albedo
+            phase_function: Arc::new(HenyeyGreenstein::new(Vector3::fill(1.0), anisotropy)),
+            emitter: Arc::new(DiffuseLight::new(emission)),
+        })
+    }
+
+    fn voxel(&self, x: usize, y: usize, z: usize) -> f64 {
+        let [nx, ny, _] = self.resolution;
+        self.density[x + nx * (y + ny * z)] as f64
+    }
+
+    /// Trilinear interpolation of the density grid
+    fn density_at(&self, point: &Vector3) -> f64 {
+        let extent = &self.bounds.max - &self.bounds.min;
+        let local = (point - &self.bounds.min) / extent;
+        let coords = [local.x, local.y, local.z];
+
+        let mut base = [0; 3];
+        let mut frac = [0.0; 3];
+        for axis in 0..3 {
+            let n = self.resolution[axis];
+            let g = (coords[axis] * n as f64 - 0.5).clamp(0.0, (n - 1) as f64);
+            base[axis] = (g.floor() as usize).min(n.saturating_sub(2));
+            frac[axis] = g - base[axis] as f64;
+        }
+
+        let [nx, ny, nz] = self.resolution;
+        let next = |axis: usize, n: usize| (base[axis] + 1).min(n - 1);
+        let (x0, y0, z0) = (base[0], base[1], base[2]);
+        let (x1, y1, z1) = (next(0, nx), next(1, ny), next(2, nz));
+        let [fx, fy, fz] = frac;
+
+        let lerp = |a: f64, b: f64, t: f64| a + (b - a) * t;
+        let c00 = lerp(self.voxel(x0, y0, z0), self.voxel(x1, y0, z0), fx);
+        let c10 = lerp(self.voxel(x0, y1, z0), self.voxel(x1, y1, z0), fx);
+        let c01 = lerp(self.voxel(x0, y0, z1), self.voxel(x1, y0, z1), fx);
+        let c11 = lerp(self.voxel(x0, y1, z1), self.voxel(x1, y1, z1), fx);
+        lerp(lerp(c00, c10, fy), lerp(c01, c11, fy), fz)
+    }
+}
+
+impl Hittable for GridMedium {
+    fn hit(&self, ray: &Ray, interval: Interval) -> Option<Hit> {
+        if self.majorant <= 0.0 {
+            return None;
+        }
+        let inside = self
+            .bounds
+            .hit(ray, Interval::new(interval.min.max(0.0), interval.max))?;
+        let (mut t, t_exit) = (inside.min, inside.max);
+        let ray_length = ray.direction.len();
+
+        // Delta tracking: sample collisions against the majorant and classify them
+        // as absorption, real scattering or null collisions
+        loop {
+            t -= (1.0 - math::rand_f64()).ln() / (self.majorant * ray_length);
+            if t >= t_exit {
+                return None;
+            }
+
+            let point = ray.at(t);
+            let density = self.density_at(&point);
+            let xi = math::rand_f64() * self.majorant;
+
+            let material = if xi < density * self.absorption {
+                &self.emitter
+            } else if xi < density * (self.absorption + self.scattering) {
+                &self.phase_function
+            } else {
+                continue;
+            };
+
+            let record = HitRecord {
+                point,
+                // arbitrary, the phase function does not depend on it
+                normal: Vector3::new(1.0, 0.0, 0.0),
+                shading_normal: Vector3::new(1.0, 0.0, 0.0),
+                tangent: None,
+                direction: ray.direction.clone(),
+                t,
+                front: true,
+                u: 0.0,
+                v: 0.0,
+                vertex_color: None,
+                barycentrics: None,
+                wavelengths: None,
+            };
+            return Some(Hit {
+                record,
+                material: material.clone(),
+            });
+        }
+    }
+
+    /// Ratio tracking: every tentative collision against the majorant keeps the share of
+    /// light the real density at it lets through
+    fn transmittance(&self, ray: &Ray, t_max: f64) -> f64 {
+        if self.majorant <= 0.0 {
+            return 1.0;
+        }
+        let Some(inside) = self.bounds.hit(ray, Interval::new(T_EPSILON, t_max)) else {
+            return 1.0;
+        };
+        let (mut t, t_exit) = (inside.min, inside.max);
+        let ray_length = ray.direction.len();
+        let extinction = self.absorption + self.scattering;
+
+        let mut transmittance: f64 = 1.0;
+        loop {
+            t -= (1.0 - math::rand_f64()).ln() / (self.majorant * ray_length);
+            if t >= t_exit || transmittance <= 0.0 {
+                return transmittance.max(0.0);
+            }
+            let density = self.density_at(&ray.at(t));
+            transmittance *= 1.0 - density * extinction / self.majorant;
+        }
+    }
+
+    fn for_each_material(&self, found: &mut dyn FnMut(&MaterialRef)) {
+        found(&self.phase_function);
+        found(&self.emitter);
+    }
+}
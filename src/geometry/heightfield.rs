@@ -0,0 +1,241 @@
+use crate::{
+    component::{
+        hit::{Hit, HitRecord},
+        ray::Ray,
+    },
+    material::MaterialRef,
+    math::{Interval, Vector3},
+};
+
+use super::{
+    aabb::Aabb,
+    triangle::{self, TriangleHit},
+    Hittable,
+};
+
+/// Terrain from a grayscale image, spanning `extent` on the xz plane from `corner`.
+/// Every pixel is a vertex and every cell between four of them is split into two triangles.
+pub struct Heightfield {
+    corner: Vector3,
+    extent: [f64; 2],
+    height_scale: f64,
+    columns: usize,
+    rows: usize,
+    heights: Vec<f64>,
+    normals: Vec<Vector3>,
+    /// Highest vertex of each cell, to skip cells the ray passes above
+    cell_max: Vec<f64>,
+    material: MaterialRef,
+}
+
+impl Heightfield {
+    /// `samples` are the image pixels in `0..=1`, row by row, at least 2 by 2
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        samples: Vec<f64>,
+        columns: usize,
+        rows: usize,
+        corner: Vector3,
+        extent: [f64; 2],
+        height_scale: f64,
+        material: MaterialRef,
+    ) -> Box<Self> {
+        let heights = samples
+            .into_iter()
+            .map(|s| corner.y + s * height_scale)
+            .collect();
+
+        let mut heightfield = Self {
+            corner,
+            extent,
+            height_scale,
+            columns,
+            rows,
+            heights,
+            normals: Vec::new(),
+            cell_max: Vec::new(),
+            material,
+        };
+        heightfield.normals = heightfield.vertex_normals();
+        heightfield.cell_max = heightfield.cell_maximums();
+
+        Box::new(heightfield)
+    }
+
+    fn cell_size(&self) -> (f64, f64) {
+        (
+            self.extent[0] / (self.columns - 1) as f64,
+            self.extent[1] / (self.rows - 1) as f64,
+        )
+    }
+
+    fn height(&self, column: usize, row: usize) -> f64 {
+        self.heights[row * self.columns + column]
+    }
+
+    fn vertex(&self, column: usize, row: usize) -> Vector3 {
+        let (dx, dz) = self.cell_size();
+        Vector3::new(
+            self.corner.x + column as f64 * dx,
+            self.height(column, row),
+            self.corner.z + row as f64 * dz,
+        )
+    }
+
+    /// Central differences of the heights
+    fn vertex_normals(&self) -> Vec<Vector3> {
+        let (dx, dz) = self.cell_size();
+        let mut normals = Vec::with_capacity(self.heights.len());
+        for row in 0..self.rows {
+            for column in 0..self.columns {
+                let (left, right) = (column.saturating_sub(1), (column + 1).min(self.columns - 1));
+                let (back, front) = (row.saturating_sub(1), (row + 1).min(self.rows - 1));
+                let slope_x = (self.height(right, row) - self.height(left, row))
+                    / ((right - left) as f64 * dx);
+                let slope_z = (self.height(column, front) - self.height(column, back))
+                    / ((front - back) as f64 * dz);
+                normals.push(Vector3::new(-slope_x, 1.0, -slope_z).normal());
+            }
+        }
+        normals
+    }
+
+    fn cell_maximums(&self) -> Vec<f64> {
+        let mut maximums = Vec::with_capacity((self.columns - 1) * (self.rows - 1));
+        for row in 0..self.rows - 1 {
+            for column in 0..self.columns - 1 {
+                maximums.push(
+                    self.height(column, row)
+                        .max(self.height(column + 1, row))
+                        .max(self.height(column, row + 1))
+                        .max(self.height(column + 1, row + 1)),
+                );
+            }
+        }
+        maximums
+    }
+
+    fn cell_hit(&self, ray: &Ray, interval: Interval, column: usize, row: usize) -> Option<Hit> {
+        let corners = [
+            (column, row),
+            (column + 1, row),
+            (column + 1, row + 1),
+            (column, row + 1),
+        ];
+        let vertices = corners.map(|(c, r)| self.vertex(c, r));
+
+        let mut closest: Option<(TriangleHit, [usize; 3])> = None;
+        for indices in [[0, 1, 2], [0, 2, 3]] {
+            let limit = closest.as_ref().map_or(interval.max, |(hit, _)| hit.t);
+            let points = indices.map(|i| &vertices[i]);
+            if let Some(hit) = triangle::intersect(ray, Interval::new(interval.min, limit), points)
+            {
+                closest = Some((hit, indices));
+            }
+        }
+        let (hit, indices) = closest?;
+
+        // smooth shading from the vertex normals
+        let normal_of = |i: usize| {
+            let (c, r) = corners[indices[i]];
+            &self.normals[r * self.columns + c]
+        };
+        let outward = hit
+            .interpolate([normal_of(0), normal_of(1), normal_of(2)])
+            .normal();
+
+        // the cells wind clockwise seen from above, so this one points up
+        let points = indices.map(|i| &vertices[i]);
+        let geometric = (points[2] - points[0])
+            .cross(&(points[1] - points[0]))
+            .normal();
+        let front = ray.direction.dot(&geometric) < 0.0;
+
+        let point = ray.at(hit.t);
+        let record = HitRecord {
+            u: (point.x - self.corner.x) / self.extent[0],
+            v: (point.z - self.corner.z) / self.extent[1],
+            vertex_color: None,
+            barycentrics: None,
+            wavelengths: None,
+            point,
+            normal: if front { geometric } else { -geometric },
+            shading_normal: if front { outward } else { -outward },
+            tangent: Some(Vector3::new(1.0, 0.0, 0.0)),
+            direction: ray.direction.clone(),
+            t: hit.t,
+            front,
+        };
+        Some(Hit {
+            record,
+            material: self.material.clone(),
+        })
+    }
+}
+
+impl Hittable for Heightfield {
+    fn hit(&self, ray: &Ray, interval: Interval) -> Option<Hit> {
+        // a negative height scale hangs the terrain below the corner
+        let peak = self.corner.y + self.height_scale;
+        let bounds = Aabb::new(
+            Vector3::new(self.corner.x, self.corner.y.min(peak), self.corner.z),
+            Vector3::new(
+                self.corner.x + self.extent[0],
+                self.corner.y.max(peak),
+                self.corner.z + self.extent[1],
+            ),
+        )
+        .hit(ray, interval)?;
+        let (dx, dz) = self.cell_size();
+        let (cells_x, cells_z) = (self.columns - 1, self.rows - 1);
+
+        let entry = ray.at(bounds.min);
+        let cell = |value: f64, size: f64, count: usize| {
+            ((value / size).floor().max(0.0) as usize).min(count - 1)
+        };
+        let mut column = cell(entry.x - self.corner.x, dx, cells_x);
+        let mut row = cell(entry.z - self.corner.z, dz, cells_z);
+
+        // 2D DDA over the cells crossed by the ray projection on the xz plane
+        let step_x: isize = if ray.direction.x >= 0.0 { 1 } else { -1 };
+        let step_z: isize = if ray.direction.z >= 0.0 { 1 } else { -1 };
+        let boundary = |index: usize, step: isize, size: f64, origin: f64| {
+            origin + (index as f64 + if step > 0 { 1.0 } else { 0.0 }) * size
+        };
+        let mut next_x =
+            (boundary(column, step_x, dx, self.corner.x) - ray.origin.x) / ray.direction.x;
+        let mut next_z =
+            (boundary(row, step_z, dz, self.corner.z) - ray.origin.z) / ray.direction.z;
+        let delta_x = (dx / ray.direction.x).abs();
+        let delta_z = (dz / ray.direction.z).abs();
+
+        let mut t_cell = bounds.min;
+        loop {
+            let t_next = next_x.min(next_z).min(bounds.max);
+
+            // skip the triangles when the ray stays above the cell
+            let lowest = ray.at(t_cell).y.min(ray.at(t_next).y);
+            if lowest <= self.cell_max[row * cells_x + column] {
+                if let Some(hit) = self.cell_hit(ray, interval, column, row) {
+                    return Some(hit);
+                }
+            }
+
+            if t_next >= bounds.max {
+                return None;
+            }
+            t_cell = t_next;
+            if next_x < next_z {
+                column = column.checked_add_signed(step_x).filter(|c| *c < cells_x)?;
+                next_x += delta_x;
+            } else {
+                row = row.checked_add_signed(step_z).filter(|r| *r < cells_z)?;
+                next_z += delta_z;
+            }
+        }
+    }
+
+    fn for_each_material(&self, found: &mut dyn FnMut(&MaterialRef)) {
+        found(&self.material);
+    }
+}
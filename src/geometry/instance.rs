@@ -0,0 +1,138 @@
+use std::sync::Arc;
+
+use crate::{
+    component::{hit::Hit, ray::Ray},
+    material::MaterialRef,
+    math::{self, Interval, Matrix4, Vector3},
+};
+
+use super::Hittable;
+
+/// Emission samples estimating how much the transform stretches the emitting surface
+const STRETCH_SAMPLES: usize = 64;
+
+/// Shared object placed in the world by an affine transform
+pub struct Instance {
+    object: Arc<dyn Hittable>,
+    to_world: Matrix4,
+    to_object: Matrix4,
+    /// Inverse transpose, for the normals
+    normal_matrix: Matrix4,
+    /// Of the emitting surface in the world
+    emitting_area: f64,
+    /// Bound on the area stretch anywhere on the surface
+    max_stretch: f64,
+}
+
+impl Instance {
+    /// `None` when the transform is singular
+    pub fn new(object: Arc<dyn Hittable>, to_world: Matrix4) -> Option<Box<Self>> {
+        let to_object = to_world.inverse()?;
+        let mut instance = Self {
+            object,
+            normal_matrix: to_object.transpose(),
+            to_world,
+            to_object,
+            emitting_area: 0.0,
+            max_stretch: 0.0,
+        };
+
+        // no two singular values multiply to more than half the squared Frobenius norm
+        instance.max_stretch = [
+            Vector3::new(1.0, 0.0, 0.0),
+            Vector3::new(0.0, 1.0, 0.0),
+            Vector3::new(0.0, 0.0, 1.0),
+        ]
+        .iter()
+        .map(|axis| instance.to_world.transform_vector(axis).quadrance())
+        .sum::<f64>()
+            / 2.0;
+        let area = instance.object.emitting_area();
+        if area > 0.0 {
+            // exact for rotations with a uniform scale, where the stretch is the same
+            // everywhere
+            let stretches: Vec<f64> = (0..STRETCH_SAMPLES)
+                .filter_map(|_| instance.object.sample_emitting())
+                .map(|hit| instance.stretch(&hit.record.normal))
+                .collect();
+            if !stretches.is_empty() {
+                instance.emitting_area =
+                    area * stretches.iter().sum::<f64>() / stretches.len() as f64;
+            }
+        }
+        Some(Box::new(instance))
+    }
+
+    /// Area in the world of a unit of area of the object around the normal
+    fn stretch(&self, normal: &Vector3) -> f64 {
+        let (a, b) = normal.normal().orthonormal_basis();
+        self.to_world
+            .transform_vector(&a)
+            .cross(&self.to_world.transform_vector(&b))
+            .len()
+    }
+
+    /// Moves a hit of the object into the world
+    fn to_world_hit(&self, hit: &mut Hit) {
+        let record = &mut hit.record;
+        record.point = self.to_world.transform_point(&record.point);
+        record.normal = self.normal_matrix.transform_vector(&record.normal).normal();
+        record.shading_normal = self
+            .normal_matrix
+            .transform_vector(&record.shading_normal)
+            .normal();
+        record.tangent = record
+            .tangent
+            .as_ref()
+            .map(|tangent| self.to_world.transform_vector(tangent).normal());
+    }
+
+    /// The direction is not normalized, so distances along both rays match
+    fn local_ray(&self, ray: &Ray) -> Ray {
+        Ray::new(
+            self.to_object.transform_point(&ray.origin),
+            self.to_object.transform_vector(&ray.direction),
+        )
+    }
+}
+
+impl Hittable for Instance {
+    fn hit(&self, ray: &Ray, interval: Interval) -> Option<Hit> {
+        let mut hit = self.object.hit(&self.local_ray(ray), interval)?;
+        self.to_world_hit(&mut hit);
+        hit.record.direction = ray.direction.clone();
+        Some(hit)
+    }
+
+    fn occluded(&self, ray: &Ray, t_max: f64) -> bool {
+        self.object.occluded(&self.local_ray(ray), t_max)
+    }
+
+    fn transmittance(&self, ray: &Ray, t_max: f64) -> f64 {
+        self.object.transmittance(&self.local_ray(ray), t_max)
+    }
+
+    fn emitting_area(&self) -> f64 {
+        self.emitting_area
+    }
+
+    /// Keeps points of the object in proportion to their stretch, so they are uniform
+    /// over the surface in the world
+    fn sample_emitting(&self) -> Option<Hit> {
+        if self.emitting_area <= 0.0 {
+            return None;
+        }
+        loop {
+            let mut hit = self.object.sample_emitting()?;
+            if math::rand_f64() * self.max_stretch <= self.stretch(&hit.record.normal) {
+                self.to_world_hit(&mut hit);
+                hit.record.direction = self.to_world.transform_vector(&hit.record.direction);
+                return Some(hit);
+            }
+        }
+    }
+
+    fn for_each_material(&self, found: &mut dyn FnMut(&MaterialRef)) {
+        self.object.for_each_material(found);
+    }
+}
@@ -0,0 +1,77 @@
+use crate::{
+    component::{hit::Hit, ray::Ray},
+    material::MaterialRef,
+    math::{self, Interval},
+};
+
+use super::{Hittable, HittableRef};
+
+pub struct HittableList {
+    objects: Vec<HittableRef>,
+    /// Summed over the objects
+    emitting_area: f64,
+}
+
+impl HittableList {
+    pub fn new(objects: Vec<HittableRef>) -> Box<Self> {
+        let emitting_area = objects.iter().map(|obj| obj.emitting_area()).sum();
+        Box::new(Self {
+            objects,
+            emitting_area,
+        })
+    }
+}
+
+impl Hittable for HittableList {
+    fn hit(&self, ray: &Ray, interval: Interval) -> Option<Hit> {
+        let mut closest = interval;
+        let mut hit = None;
+        for obj in self.objects.iter() {
+            if let Some(res) = obj.hit(ray, closest) {
+                closest.max = res.record.t;
+                hit = Some(res);
+            }
+        }
+        hit
+    }
+
+    fn occluded(&self, ray: &Ray, t_max: f64) -> bool {
+        self.objects.iter().any(|obj| obj.occluded(ray, t_max))
+    }
+
+    fn transmittance(&self, ray: &Ray, t_max: f64) -> f64 {
+        self.objects
+            .iter()
+            .map(|obj| obj.transmittance(ray, t_max))
+            .product()
+    }
+
+    fn emitting_area(&self) -> f64 {
+        self.emitting_area
+    }
+
+    /// Picks an object by its emitting area first
+    fn sample_emitting(&self) -> Option<Hit> {
+        let mut pick = math::rand_f64() * self.emitting_area;
+        let mut last = None;
+        for obj in self.objects.iter() {
+            let area = obj.emitting_area();
+            if area <= 0.0 {
+                continue;
+            }
+            if pick < area {
+                return obj.sample_emitting();
+            }
+            pick -= area;
+            last = Some(obj);
+        }
+        // only rounding errors get past the end
+        last?.sample_emitting()
+    }
+
+    fn for_each_material(&self, found: &mut dyn FnMut(&MaterialRef)) {
+        self.objects
+            .iter()
+            .for_each(|object| object.for_each_material(found));
+    }
+}
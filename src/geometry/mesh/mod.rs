@@ -0,0 +1,359 @@
+use crate::{
+    component::{
+        hit::{Hit, HitRecord},
+        ray::Ray,
+    },
+    material::MaterialRef,
+    math::{self, Interval, Vector3},
+    texture::TextureRef,
+};
+
+use super::{
+    aabb::Aabb,
+    triangle::{self, TriangleHit},
+    Hittable,
+};
+
+pub mod ply;
+pub mod stl;
+
+const LEAF_SIZE: usize = 4;
+
+/// Indexed triangles as read from a mesh file
+#[derive(Default)]
+pub struct MeshData {
+    pub positions: Vec<Vector3>,
+    pub normals: Option<Vec<Vector3>>,
+    pub colors: Option<Vec<Vector3>>,
+    pub uvs: Option<Vec<(f64, f64)>>,
+    pub triangles: Vec<[usize; 3]>,
+}
+
+impl MeshData {
+    pub fn transform(&mut self, scale: f64, offset: &Vector3) {
+        for position in self.positions.iter_mut() {
+            *position = &*position * scale + offset;
+        }
+        if scale < 0.0 {
+            for normal in self.normals.iter_mut().flatten() {
+                *normal = -&*normal;
+            }
+        }
+    }
+
+    pub fn area(&self) -> f64 {
+        self.triangles
+            .iter()
+            .map(|triangle| {
+                let [a, b, c] = triangle.map(|i| &self.positions[i]);
+                (b - a).cross(&(c - a)).len() / 2.0
+            })
+            .sum()
+    }
+
+    /// Area weighted average of the faces around each vertex
+    pub fn compute_normals(&mut self) {
+        let mut normals = vec![Vector3::fill(0.0); self.positions.len()];
+        for triangle in &self.triangles {
+            let [a, b, c] = triangle.map(|i| &self.positions[i]);
+            let face = (b - a).cross(&(c - a));
+            for &i in triangle {
+                normals[i] += &face;
+            }
+        }
+        self.normals = Some(
+            normals
+                .into_iter()
+                .map(|n| if n.near_zero() { n } else { n.normal() })
+                .collect(),
+        );
+    }
+}
+
+/// Node of the flattened bounding volume hierarchy. Leaves hold `count` triangles
+/// from `start` in the triangle order, inner nodes have their left child right
+/// after them and the right one at `right`.
+struct Node {
+    bounds: Aabb,
+    start: usize,
+    count: usize,
+    right: usize,
+}
+
+pub struct Mesh {
+    data: MeshData,
+    nodes: Vec<Node>,
+    order: Vec<usize>,
+    material: MaterialRef,
+    /// Tangent space normals, needs texture coordinates
+    normal_map: Option<TextureRef>,
+    /// Running sum of the triangle areas, only for light emitting materials
+    emitting: Vec<f64>,
+}
+
+impl Mesh {
+    pub fn new(data: MeshData, material: MaterialRef, normal_map: Option<TextureRef>) -> Box<Self> {
+        let emitting = if material.emissive() {
+            let mut total = 0.0;
+            data.triangles
+                .iter()
+                .map(|triangle| {
+                    let [a, b, c] = triangle.map(|i| &data.positions[i]);
+                    total += (b - a).cross(&(c - a)).len() / 2.0;
+                    total
+                })
+                .collect()
+        } else {
+            Vec::new()
+        };
+        let mut mesh = Self {
+            order: (0..data.triangles.len()).collect(),
+            data,
+            nodes: Vec::new(),
+            material,
+            normal_map,
+            emitting,
+        };
+        if !mesh.order.is_empty() {
+            let centroids = mesh
+                .data
+                .triangles
+                .iter()
+                .map(|t| mesh.triangle_bounds(t).centroid())
+                .collect::<Vec<_>>();
+            mesh.build(&centroids, 0, mesh.order.len());
+        }
+        Box::new(mesh)
+    }
+
+    fn triangle_bounds(&self, triangle: &[usize; 3]) -> Aabb {
+        let mut bounds = Aabb::empty();
+        for &i in triangle {
+            bounds.grow(&self.data.positions[i]);
+        }
+        bounds
+    }
+
+    /// Median split on the widest axis of the centroids
+    fn build(&mut self, centroids: &[Vector3], start: usize, end: usize) -> usize {
+        let mut bounds = Aabb::empty();
+        let mut centroid_bounds = Aabb::empty();
+        for &i in &self.order[start..end] {
+            let triangle = self.triangle_bounds(&self.data.triangles[i]);
+            bounds.grow(&triangle.min);
+            bounds.grow(&triangle.max);
+            centroid_bounds.grow(&centroids[i]);
+        }
+
+        let index = self.nodes.len();
+        self.nodes.push(Node {
+            bounds,
+            start,
+            count: end - start,
+            right: 0,
+        });
+        if end - start <= LEAF_SIZE {
+            return index;
+        }
+
+        let extent = &centroid_bounds.max - &centroid_bounds.min;
+        let axis_of = |v: &Vector3| {
+            if extent.x >= extent.y && extent.x >= extent.z {
+                v.x
+            } else if extent.y >= extent.z {
+                v.y
+            } else {
+                v.z
+            }
+        };
+        let mid = (start + end) / 2;
+        self.order[start..end].select_nth_unstable_by(mid - start, |a, b| {
+            axis_of(&centroids[*a]).total_cmp(&axis_of(&centroids[*b]))
+        });
+
+        self.build(centroids, start, mid);
+        let right = self.build(centroids, mid, end);
+        self.nodes[index].count = 0;
+        self.nodes[index].right = right;
+        index
+    }
+
+    fn triangle_hit(&self, ray: &Ray, interval: Interval, index: usize) -> Option<TriangleHit> {
+        let positions = self.data.triangles[index].map(|i| &self.data.positions[i]);
+        triangle::intersect(ray, interval, positions)
+    }
+
+    fn record(&self, ray: &Ray, index: usize, hit: TriangleHit) -> HitRecord {
+        let indices = self.data.triangles[index];
+        let positions = indices.map(|i| &self.data.positions[i]);
+        let geometric = (positions[1] - positions[0])
+            .cross(&(positions[2] - positions[0]))
+            .normal();
+        let front = ray.direction.dot(&geometric) < 0.0;
+
+        let mut shading = match &self.data.normals {
+            Some(normals) => {
+                let normal = hit.interpolate(indices.map(|i| &normals[i]));
+                if normal.near_zero() {
+                    geometric.clone()
+                } else {
+                    normal.normal()
+                }
+            }
+            None => geometric.clone(),
+        };
+
+        let (u, v) = match &self.data.uvs {
+            Some(uvs) => {
+                let uv = indices.map(|i| Vector3::new(uvs[i].0, uvs[i].1, 0.0));
+                let uv = hit.interpolate([&uv[0], &uv[1], &uv[2]]);
+                (uv.x, uv.y)
+            }
+            None => (0.0, 0.0),
+        };
+        let vertex_color = self
+            .data
+            .colors
+            .as_ref()
+            .map(|colors| hit.interpolate(indices.map(|i| &colors[i])));
+        let derivatives = self.derivatives(indices);
+
+        let mut record = HitRecord {
+            point: ray.at(hit.t),
+            normal: if front { geometric } else { -geometric },
+            shading_normal: Vector3::fill(0.0),
+            tangent: derivatives
+                .as_ref()
+                .filter(|(dpdu, _)| !dpdu.near_zero())
+                .map(|(dpdu, _)| dpdu.normal()),
+            direction: ray.direction.clone(),
+            t: hit.t,
+            front,
+            u,
+            v,
+            vertex_color,
+            barycentrics: Some(Vector3::new(1.0 - hit.b1 - hit.b2, hit.b1, hit.b2)),
+            wavelengths: None,
+        };
+        if let Some(mapped) =
+            derivatives.and_then(|derivatives| self.mapped_normal(&record, derivatives, &shading))
+        {
+            shading = mapped;
+        }
+        record.shading_normal = if front { shading } else { -shading };
+        record
+    }
+
+    /// Derivatives of the position along `u` and `v` over a triangle, `None` without
+    /// texture coordinates or when they are degenerate
+    fn derivatives(&self, indices: [usize; 3]) -> Option<(Vector3, Vector3)> {
+        let uvs = self.data.uvs.as_ref()?;
+
+        let [p0, p1, p2] = indices.map(|i| &self.data.positions[i]);
+        let [uv0, uv1, uv2] = indices.map(|i| uvs[i]);
+        let (du1, dv1) = (uv1.0 - uv0.0, uv1.1 - uv0.1);
+        let (du2, dv2) = (uv2.0 - uv0.0, uv2.1 - uv0.1);
+        let determinant = du1 * dv2 - du2 * dv1;
+        if determinant.abs() < 1e-12 {
+            return None;
+        }
+
+        let (edge1, edge2) = (p1 - p0, p2 - p0);
+        Some((
+            (&edge1 * dv2 - &edge2 * dv1) / determinant,
+            (&edge2 * du1 - &edge1 * du2) / determinant,
+        ))
+    }
+
+    /// Normal map sample in the tangent frame built from the texture coordinates
+    fn mapped_normal(
+        &self,
+        record: &HitRecord,
+        (tangent, bitangent): (Vector3, Vector3),
+        normal: &Vector3,
+    ) -> Option<Vector3> {
+        let normal_map = self.normal_map.as_ref()?;
+
+        // Gram-Schmidt, keeping the handedness of the UV mapping
+        let tangent = &tangent - normal * normal.dot(&tangent);
+        if tangent.near_zero() {
+            return None;
+        }
+        let tangent = tangent.normal();
+        let mut bitangent_ortho = normal.cross(&tangent);
+        if bitangent_ortho.dot(&bitangent) < 0.0 {
+            bitangent_ortho = -bitangent_ortho;
+        }
+
+        let sample = normal_map.value(record) * 2.0 - 1.0;
+        let mapped = tangent * sample.x + bitangent_ortho * sample.y + normal * sample.z;
+        (!mapped.near_zero()).then(|| mapped.normal())
+    }
+}
+
+impl Hittable for Mesh {
+    fn hit(&self, ray: &Ray, interval: Interval) -> Option<Hit> {
+        if self.nodes.is_empty() {
+            return None;
+        }
+
+        let mut closest = interval;
+        let mut found = None;
+        let mut stack = vec![0];
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            if node.bounds.hit(ray, closest).is_none() {
+                continue;
+            }
+
+            if node.count == 0 {
+                stack.push(node.right);
+                stack.push(index + 1);
+                continue;
+            }
+            for &triangle in &self.order[node.start..node.start + node.count] {
+                if let Some(hit) = self.triangle_hit(ray, closest, triangle) {
+                    closest.max = hit.t;
+                    found = Some((triangle, hit));
+                }
+            }
+        }
+
+        let (triangle, hit) = found?;
+        Some(Hit {
+            record: self.record(ray, triangle, hit),
+            material: self.material.clone(),
+        })
+    }
+
+    fn emitting_area(&self) -> f64 {
+        self.emitting.last().copied().unwrap_or(0.0)
+    }
+
+    fn sample_emitting(&self) -> Option<Hit> {
+        let total = self.emitting.last()?;
+        let pick = math::rand_f64() * total;
+        let index = self
+            .emitting
+            .partition_point(|&sum| sum <= pick)
+            .min(self.emitting.len() - 1);
+
+        // uniform over the triangle
+        let [a, b, c] = self.data.triangles[index].map(|i| &self.data.positions[i]);
+        let root = math::rand_f64().sqrt();
+        let (b0, b1) = (1.0 - root, math::rand_f64() * root);
+        let point = a * b0 + b * b1 + c * (1.0 - b0 - b1);
+        let outward = (b - a).cross(&(c - a)).normal();
+
+        let ray = Ray::new(&point + &outward, -outward);
+        let hit = self.triangle_hit(&ray, Interval::universe(), index)?;
+        Some(Hit {
+            record: self.record(&ray, index, hit),
+            material: self.material.clone(),
+        })
+    }
+
+    fn for_each_material(&self, found: &mut dyn FnMut(&MaterialRef)) {
+        found(&self.material);
+    }
+}
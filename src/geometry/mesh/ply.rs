@@ -0,0 +1,303 @@
+use crate::math::Vector3;
+
+use super::MeshData;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Format {
+    Ascii,
+    BinaryLittleEndian,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Scalar {
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    F32,
+    F64,
+}
+
+impl Scalar {
+    fn parse(name: &str) -> Result<Self, String> {
+        Ok(match name {
+            "char" | "int8" => Self::I8,
+            "uchar" | "uint8" => Self::U8,
+            "short" | "int16" => Self::I16,
+            "ushort" | "uint16" => Self::U16,
+            "int" | "int32" => Self::I32,
+            "uint" | "uint32" => Self::U32,
+            "float" | "float32" => Self::F32,
+            "double" | "float64" => Self::F64,
+            _ => return Err(format!("unsupported property type '{name}'")),
+        })
+    }
+
+    fn size(self) -> usize {
+        match self {
+            Self::I8 | Self::U8 => 1,
+            Self::I16 | Self::U16 => 2,
+            Self::I32 | Self::U32 | Self::F32 => 4,
+            Self::F64 => 8,
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Property {
+    Scalar(Scalar, String),
+    List(Scalar, Scalar, String),
+}
+
+#[derive(Debug)]
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<Property>,
+}
+
+struct Reader<'a> {
+    format: Format,
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl Reader<'_> {
+    fn token(&mut self) -> Result<&str, String> {
+        let rest = &self.bytes[self.position..];
+        let start = rest
+            .iter()
+            .position(|b| !b.is_ascii_whitespace())
+            .ok_or("unexpected end of file")?;
+        let length = rest[start..]
+            .iter()
+            .position(|b| b.is_ascii_whitespace())
+            .unwrap_or(rest.len() - start);
+        self.position += start + length;
+
+        std::str::from_utf8(&rest[start..start + length]).map_err(|e| e.to_string())
+    }
+
+    fn read(&mut self, scalar: Scalar) -> Result<f64, String> {
+        if self.format == Format::Ascii {
+            let token = self.token()?;
+            return token
+                .parse()
+                .map_err(|_| format!("invalid number '{token}'"));
+        }
+
+        let size = scalar.size();
+        let bytes = self
+            .bytes
+            .get(self.position..self.position + size)
+            .ok_or("unexpected end of file")?;
+        self.position += size;
+
+        Ok(match scalar {
+            Scalar::I8 => bytes[0] as i8 as f64,
+            Scalar::U8 => bytes[0] as f64,
+            Scalar::I16 => i16::from_le_bytes([bytes[0], bytes[1]]) as f64,
+            Scalar::U16 => u16::from_le_bytes([bytes[0], bytes[1]]) as f64,
+            Scalar::I32 => i32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f64,
+            Scalar::U32 => u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f64,
+            Scalar::F32 => f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f64,
+            Scalar::F64 => f64::from_le_bytes(bytes.try_into().unwrap()),
+        })
+    }
+}
+
+fn parse_header(bytes: &[u8]) -> Result<(Format, Vec<Element>, usize), String> {
+    const END: &[u8] = b"end_header";
+    let end = bytes
+        .windows(END.len())
+        .position(|w| w == END)
+        .ok_or("missing end_header")?;
+    let body = bytes[end..]
+        .iter()
+        .position(|b| *b == b'\n')
+        .map(|p| end + p + 1)
+        .unwrap_or(bytes.len());
+    let header = std::str::from_utf8(&bytes[..end]).map_err(|e| e.to_string())?;
+
+    let mut lines = header.lines().map(str::trim);
+    if lines.next() != Some("ply") {
+        return Err("missing 'ply' magic number".into());
+    }
+
+    let mut format = None;
+    let mut elements = Vec::<Element>::new();
+    for line in lines {
+        let words = line.split_whitespace().collect::<Vec<_>>();
+        match words.as_slice() {
+            [] | ["comment", ..] | ["obj_info", ..] => {}
+            ["format", "ascii", _] => format = Some(Format::Ascii),
+            ["format", "binary_little_endian", _] => format = Some(Format::BinaryLittleEndian),
+            ["format", other, _] => return Err(format!("unsupported format '{other}'")),
+            ["element", name, count] => elements.push(Element {
+                name: name.to_string(),
+                count: count
+                    .parse()
+                    .map_err(|_| format!("invalid element count '{count}'"))?,
+                properties: Vec::new(),
+            }),
+            ["property", "list", count, item, name] => elements
+                .last_mut()
+                .ok_or("property before any element")?
+                .properties
+                .push(Property::List(
+                    Scalar::parse(count)?,
+                    Scalar::parse(item)?,
+                    name.to_string(),
+                )),
+            ["property", scalar, name] => elements
+                .last_mut()
+                .ok_or("property before any element")?
+                .properties
+                .push(Property::Scalar(Scalar::parse(scalar)?, name.to_string())),
+            _ => return Err(format!("invalid header line '{line}'")),
+        }
+    }
+
+    Ok((format.ok_or("missing format")?, elements, body))
+}
+
+/// Loads positions, optional normals, colors and texture coordinates and the faces, fan triangulated
+pub fn parse(bytes: &[u8]) -> Result<MeshData, String> {
+    let (format, elements, body) = parse_header(bytes)?;
+    let mut reader = Reader {
+        format,
+        bytes,
+        position: body,
+    };
+
+    let mut data = MeshData::default();
+    let mut normals = Vec::new();
+    let mut colors = Vec::new();
+    let mut uvs = Vec::new();
+
+    for element in &elements {
+        let is_vertex = element.name == "vertex";
+        let is_face = element.name == "face";
+
+        for _ in 0..element.count {
+            let mut position = [0.0; 3];
+            let mut normal = [None; 3];
+            let mut color = [None; 3];
+            let mut uv = [None; 2];
+            let mut face = None;
+
+            for property in &element.properties {
+                match property {
+                    Property::Scalar(scalar, name) => {
+                        let value = reader.read(*scalar)?;
+                        if !is_vertex {
+                            continue;
+                        }
+                        // 8 bit colors are gamma encoded, like image textures
+                        let channel = match scalar {
+                            Scalar::U8 => (value / 255.0).powi(2),
+                            _ => value,
+                        };
+                        match name.as_str() {
+                            "x" => position[0] = value,
+                            "y" => position[1] = value,
+                            "z" => position[2] = value,
+                            "nx" => normal[0] = Some(value),
+                            "ny" => normal[1] = Some(value),
+                            "nz" => normal[2] = Some(value),
+                            "red" | "r" => color[0] = Some(channel),
+                            "green" | "g" => color[1] = Some(channel),
+                            "blue" | "b" => color[2] = Some(channel),
+                            "u" | "s" | "texture_u" | "texture_s" => uv[0] = Some(value),
+                            "v" | "t" | "texture_v" | "texture_t" => uv[1] = Some(value),
+                            // scanner extras with nothing to shade
+                            "alpha" | "confidence" | "intensity" => {}
+                            _ => return Err(format!("unsupported vertex property '{name}'")),
+                        }
+                    }
+                    Property::List(count, item, name) => {
+                        let length = reader.read(*count)? as usize;
+                        let values = (0..length)
+                            .map(|_| reader.read(*item))
+                            .collect::<Result<Vec<_>, _>>()?;
+                        match (element.name.as_str(), name.as_str()) {
+                            ("face", "vertex_indices" | "vertex_index") => {
+                                let indices = values
+                                    .into_iter()
+                                    .map(|v| {
+                                        if v >= 0.0 && v.fract() == 0.0 {
+                                            Ok(v as usize)
+                                        } else {
+                                            Err(format!("invalid vertex index {v}"))
+                                        }
+                                    })
+                                    .collect::<Result<Vec<_>, _>>()?;
+                                face = Some(indices);
+                            }
+                            (element, name) if is_vertex || is_face => {
+                                return Err(format!(
+                                    "unsupported list property '{name}' on element '{element}'"
+                                ))
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+            }
+
+            if is_vertex {
+                data.positions
+                    .push(Vector3::new(position[0], position[1], position[2]));
+                if let [Some(x), Some(y), Some(z)] = normal {
+                    normals.push(Vector3::new(x, y, z));
+                }
+                if let [Some(r), Some(g), Some(b)] = color {
+                    colors.push(Vector3::new(r, g, b));
+                }
+                if let [Some(u), Some(v)] = uv {
+                    uvs.push((u, v));
+                }
+            }
+            if is_face {
+                let face = face.ok_or("face element without vertex_indices")?;
+                for i in 1..face.len().saturating_sub(1) {
+                    data.triangles.push([face[0], face[i], face[i + 1]]);
+                }
+            }
+        }
+    }
+
+    if let Some(index) = data
+        .triangles
+        .iter()
+        .flatten()
+        .find(|i| **i >= data.positions.len())
+    {
+        return Err(format!(
+            "face references vertex {index}, but there are only {}",
+            data.positions.len()
+        ));
+    }
+    if !normals.is_empty() {
+        if normals.len() != data.positions.len() {
+            return Err("only some vertices have a normal".into());
+        }
+        data.normals = Some(normals);
+    }
+    if !colors.is_empty() {
+        if colors.len() != data.positions.len() {
+            return Err("only some vertices have a red, green and blue color".into());
+        }
+        data.colors = Some(colors);
+    }
+    if !uvs.is_empty() {
+        if uvs.len() != data.positions.len() {
+            return Err("only some vertices have texture coordinates".into());
+        }
+        data.uvs = Some(uvs);
+    }
+
+    Ok(data)
+}
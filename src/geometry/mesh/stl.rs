@@ -0,0 +1,67 @@
+use crate::math::Vector3;
+
+use super::MeshData;
+
+/// Binary STL is an 80 byte header, a triangle count and 50 bytes per triangle:
+/// normal, three vertices and an attribute
+fn parse_binary(bytes: &[u8]) -> Option<MeshData> {
+    let count = u32::from_le_bytes(bytes.get(80..84)?.try_into().ok()?) as usize;
+    if bytes.len() != 84 + count * 50 {
+        return None;
+    }
+
+    let float =
+        |offset: usize| f32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap()) as f64;
+    let mut data = MeshData::default();
+    for triangle in 0..count {
+        let start = 84 + triangle * 50 + 12;
+        for vertex in 0..3 {
+            let offset = start + vertex * 12;
+            data.positions.push(Vector3::new(
+                float(offset),
+                float(offset + 4),
+                float(offset + 8),
+            ));
+        }
+        let first = triangle * 3;
+        data.triangles.push([first, first + 1, first + 2]);
+    }
+    Some(data)
+}
+
+fn parse_ascii(text: &str) -> Result<MeshData, String> {
+    let mut data = MeshData::default();
+    let mut words = text.split_whitespace();
+    while let Some(word) = words.next() {
+        if word != "vertex" {
+            continue;
+        }
+        let mut coordinate = || -> Result<f64, String> {
+            let token = words.next().ok_or("unexpected end of file")?;
+            token
+                .parse()
+                .map_err(|_| format!("invalid coordinate '{token}'"))
+        };
+        data.positions
+            .push(Vector3::new(coordinate()?, coordinate()?, coordinate()?));
+    }
+
+    if data.positions.len() % 3 != 0 {
+        return Err("vertex count is not a multiple of three".into());
+    }
+    data.triangles = (0..data.positions.len() / 3)
+        .map(|t| [t * 3, t * 3 + 1, t * 3 + 2])
+        .collect();
+    Ok(data)
+}
+
+/// Binary files may also start with `solid`, so the binary layout is checked first
+pub fn parse(bytes: &[u8]) -> Result<MeshData, String> {
+    if let Some(data) = parse_binary(bytes) {
+        return Ok(data);
+    }
+    if !bytes.starts_with(b"solid") {
+        return Err("neither a binary nor an ASCII STL file".into());
+    }
+    parse_ascii(std::str::from_utf8(bytes).map_err(|e| e.to_string())?)
+}
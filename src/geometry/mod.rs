@@ -1,9 +1,76 @@
-use crate::component::{hit::Hit, ray::Ray};
+use crate::{
+    component::{hit::Hit, ray::Ray},
+    material::MaterialRef,
+    math::Interval,
+};
 
+pub mod aabb;
+pub mod alpha_mask;
+pub mod constant_medium;
+pub mod csg;
+pub mod cuboid;
+pub mod grid_medium;
+pub mod heightfield;
+pub mod instance;
+pub mod list;
+pub mod mesh;
+pub mod sdf;
 pub mod sphere;
+pub mod triangle;
+
+/// Stretch of a ray line spent inside a solid object
+pub struct Span {
+    pub enter: Hit,
+    pub exit: Hit,
+}
+
+/// Smallest distance accepted for scattered rays, avoids self intersection
+pub const T_EPSILON: f64 = 0.001;
 
 pub trait Hittable: Send + Sync + 'static {
-    fn hit(&self, ray: &Ray) -> Option<Hit>;
+    fn hit(&self, ray: &Ray, interval: Interval) -> Option<Hit>;
+
+    /// Whether anything blocks the ray before `t_max`, without building the hit
+    fn occluded(&self, ray: &Ray, t_max: f64) -> bool {
+        self.hit(ray, Interval::new(T_EPSILON, t_max)).is_some()
+    }
+
+    /// Share of the light getting through the object along the ray before `t_max`, media
+    /// let part of it through
+    fn transmittance(&self, ray: &Ray, t_max: f64) -> f64 {
+        if self.occluded(ray, t_max) {
+            0.0
+        } else {
+            1.0
+        }
+    }
+
+    /// Every span of the whole ray line inside the object, sorted along the ray.
+    /// Only solids enclosing a volume report them.
+    fn spans(&self, _ray: &Ray) -> Vec<Span> {
+        Vec::new()
+    }
+
+    /// Surface area of objects with a light emitting material, zero for the rest
+    fn emitting_area(&self) -> f64 {
+        0.0
+    }
+
+    /// Point picked uniformly over the emitting surface, hit from outside
+    fn sample_emitting(&self) -> Option<Hit> {
+        None
+    }
+
+    /// Calls `found` with every material of the object, in the same order every run
+    fn for_each_material(&self, found: &mut dyn FnMut(&MaterialRef));
+}
+
+/// First span boundary inside the interval
+fn first_boundary(spans: Vec<Span>, interval: Interval) -> Option<Hit> {
+    spans
+        .into_iter()
+        .flat_map(|span| [span.enter, span.exit])
+        .find(|hit| interval.surrounds(hit.record.t))
 }
 
 pub type HittableRef = Box<dyn Hittable>;
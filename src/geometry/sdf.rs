@@ -0,0 +1,211 @@
+use serde::Deserialize;
+
+use crate::{
+    component::{
+        hit::{Hit, HitRecord},
+        ray::Ray,
+    },
+    material::MaterialRef,
+    math::{Interval, Vector3},
+};
+
+use super::Hittable;
+
+const MAX_STEPS: u32 = 512;
+const HIT_DISTANCE: f64 = 1e-5;
+const GRADIENT_STEP: f64 = 1e-5;
+
+/// Distance function tree, primitives are centered at the origin
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SdfNode {
+    Sphere {
+        radius: f64,
+    },
+    Box {
+        half_size: Vector3,
+    },
+    RoundBox {
+        half_size: Vector3,
+        radius: f64,
+    },
+    /// Lying on the xz plane
+    Torus {
+        major_radius: f64,
+        minor_radius: f64,
+    },
+    SmoothUnion {
+        left: Box<SdfNode>,
+        right: Box<SdfNode>,
+        smoothness: f64,
+    },
+    /// Infinite repetition of the shape, a zero period leaves that axis alone
+    Repeat {
+        period: Vector3,
+        shape: Box<SdfNode>,
+    },
+    /// Rotation around the y axis proportional to the height, in radians per unit
+    Twist {
+        rate: f64,
+        shape: Box<SdfNode>,
+    },
+    Translate {
+        offset: Vector3,
+        shape: Box<SdfNode>,
+    },
+}
+
+fn box_distance(point: &Vector3, half_size: &Vector3) -> f64 {
+    let q = Vector3::new(
+        point.x.abs() - half_size.x,
+        point.y.abs() - half_size.y,
+        point.z.abs() - half_size.z,
+    );
+    let outside = Vector3::new(q.x.max(0.0), q.y.max(0.0), q.z.max(0.0)).len();
+    let inside = q.x.max(q.y).max(q.z).min(0.0);
+    outside + inside
+}
+
+fn repeat_axis(value: f64, period: f64) -> f64 {
+    if period <= 0.0 {
+        return value;
+    }
+    value - period * (value / period).round()
+}
+
+impl SdfNode {
+    pub fn distance(&self, point: &Vector3) -> f64 {
+        match self {
+            Self::Sphere { radius } => point.len() - radius,
+            Self::Box { half_size } => box_distance(point, half_size),
+            Self::RoundBox { half_size, radius } => {
+                box_distance(point, &(half_size - *radius)) - radius
+            }
+            Self::Torus {
+                major_radius,
+                minor_radius,
+            } => {
+                let ring = (point.x * point.x + point.z * point.z).sqrt() - major_radius;
+                (ring * ring + point.y * point.y).sqrt() - minor_radius
+            }
+            Self::SmoothUnion {
+                left,
+                right,
+                smoothness,
+            } => {
+                let a = left.distance(point);
+                let b = right.distance(point);
+                let k = smoothness.max(1e-9);
+                let h = (0.5 + 0.5 * (b - a) / k).clamp(0.0, 1.0);
+                b + (a - b) * h - k * h * (1.0 - h)
+            }
+            Self::Repeat { period, shape } => shape.distance(&Vector3::new(
+                repeat_axis(point.x, period.x),
+                repeat_axis(point.y, period.y),
+                repeat_axis(point.z, period.z),
+            )),
+            Self::Twist { rate, shape } => {
+                let (sin, cos) = (rate * point.y).sin_cos();
+                shape.distance(&Vector3::new(
+                    cos * point.x - sin * point.z,
+                    point.y,
+                    sin * point.x + cos * point.z,
+                ))
+            }
+            Self::Translate { offset, shape } => shape.distance(&(point - offset)),
+        }
+    }
+}
+
+/// Procedural shape rendered by sphere tracing its distance function
+pub struct Sdf {
+    shape: SdfNode,
+    max_distance: f64,
+    step_scale: f64,
+    material: MaterialRef,
+}
+
+impl Sdf {
+    /// `step_scale` below 1 keeps the march safe for distortions like `Twist`
+    /// that are not exact distances
+    pub fn new(
+        shape: SdfNode,
+        max_distance: f64,
+        step_scale: f64,
+        material: MaterialRef,
+    ) -> Box<Self> {
+        Box::new(Self {
+            shape,
+            max_distance,
+            step_scale: step_scale.clamp(0.01, 1.0),
+            material,
+        })
+    }
+
+    /// Tetrahedron central differences
+    fn gradient(&self, point: &Vector3) -> Vector3 {
+        let h = GRADIENT_STEP;
+        [
+            Vector3::new(1.0, -1.0, -1.0),
+            Vector3::new(-1.0, -1.0, 1.0),
+            Vector3::new(-1.0, 1.0, -1.0),
+            Vector3::new(1.0, 1.0, 1.0),
+        ]
+        .into_iter()
+        .fold(Vector3::fill(0.0), |acc, k| {
+            let d = self.shape.distance(&(point + &k * h));
+            acc + k * d
+        })
+        .normal()
+    }
+}
+
+impl Hittable for Sdf {
+    fn hit(&self, ray: &Ray, interval: Interval) -> Option<Hit> {
+        let ray_length = ray.direction.len();
+        let t_max = interval.max.min(self.max_distance / ray_length);
+
+        let mut t = interval.min.max(0.0);
+        for _ in 0..MAX_STEPS {
+            if t >= t_max {
+                return None;
+            }
+
+            let point = ray.at(t);
+            // rays refracted inside the shape march on negative distances
+            let distance = self.shape.distance(&point).abs();
+            if distance < HIT_DISTANCE && interval.surrounds(t) {
+                let outward = self.gradient(&point);
+                let front = ray.direction.dot(&outward) < 0.0;
+                let normal = if front { outward } else { -outward };
+
+                let record = HitRecord {
+                    point,
+                    shading_normal: normal.clone(),
+                    tangent: None,
+                    normal,
+                    direction: ray.direction.clone(),
+                    t,
+                    front,
+                    u: 0.0,
+                    v: 0.0,
+                    vertex_color: None,
+                    barycentrics: None,
+                    wavelengths: None,
+                };
+                return Some(Hit {
+                    record,
+                    material: self.material.clone(),
+                });
+            }
+
+            t += distance.max(HIT_DISTANCE) * self.step_scale / ray_length;
+        }
+
+        None
+    }
+
+    fn for_each_material(&self, found: &mut dyn FnMut(&MaterialRef)) {
+        found(&self.material);
+    }
+}
@@ -1,68 +1,130 @@
-use std::sync::Arc;
+use std::{f64::consts::PI, sync::Arc};
 
 use crate::{
     component::{
         hit::{Hit, HitRecord},
         ray::Ray,
     },
-    material::Material,
-    math::Vector3,
+    material::{Material, MaterialRef},
+    math::{Interval, Vector3},
 };
 
-use super::Hittable;
+use super::{Hittable, Span, T_EPSILON};
 
-pub struct Sphere<M: Material> {
+pub struct Sphere {
     center: Vector3,
     radius: f64,
-    material: Arc<M>,
+    material: MaterialRef,
 }
 
-impl<M: Material> Sphere<M> {
-    pub fn new(center: Vector3, radius: f64, material: M) -> Box<Self> {
+impl Sphere {
+    pub fn new<M: Material>(center: Vector3, radius: f64, material: M) -> Box<Self> {
+        Self::with_material(center, radius, Arc::new(material))
+    }
+
+    pub fn with_material(center: Vector3, radius: f64, material: MaterialRef) -> Box<Self> {
         Box::new(Self {
             center,
             radius,
-            material: Arc::new(material),
+            material,
         })
     }
 }
 
-impl<M: Material> Hittable for Sphere<M> {
-    fn hit(&self, ray: &Ray) -> Option<Hit> {
-        let t_min = 0.001;
-        let t_max = f64::INFINITY;
-
+impl Sphere {
+    /// Closest root of the ray/sphere equation inside the interval
+    fn root(&self, ray: &Ray, interval: Interval) -> Option<f64> {
         let oc = &ray.origin - &self.center;
         let a = ray.direction.quadrance();
         let half_b = oc.dot(&ray.direction);
         let c = oc.quadrance() - self.radius * self.radius;
         let discriminant = half_b * half_b - a * c;
-        if discriminant > 0.0 {
-            let sqrtd = discriminant.sqrt();
-            let mut root = (-half_b - sqrtd) / a;
-            if root <= t_min || root >= t_max {
-                root = (-half_b + sqrtd) / a;
-                if root <= t_min || root >= t_max {
-                    return None;
-                }
-            }
-            let point = ray.at(root);
-            let normal = (&point - &self.center) / self.radius;
-            let front = ray.direction.dot(&normal) < 0.0;
-
-            let record = HitRecord {
-                point,
-                normal: if front { normal } else { -normal },
-                direction: ray.direction.clone(),
-                t: root,
-                front,
-            };
-            return Some(Hit {
-                record,
-                material: self.material.clone(),
-            });
+        if discriminant <= 0.0 {
+            return None;
         }
 
-        None
+        let sqrtd = discriminant.sqrt();
+        let root = (-half_b - sqrtd) / a;
+        if interval.surrounds(root) {
+            return Some(root);
+        }
+        let root = (-half_b + sqrtd) / a;
+        interval.surrounds(root).then_some(root)
+    }
+
+    fn hit_at(&self, ray: &Ray, root: f64) -> Hit {
+        let point = ray.at(root);
+        let normal = (&point - &self.center) / self.radius;
+        let front = ray.direction.dot(&normal) < 0.0;
+
+        // latitude/longitude coordinates, v going up from the south pole
+        let u = ((-normal.z).atan2(normal.x) + PI) / (2.0 * PI);
+        let v = (-normal.y).clamp(-1.0, 1.0).acos() / PI;
+        // along the parallel, vanishing at the poles
+        let tangent = Vector3::new(normal.z, 0.0, -normal.x);
+        let normal = if front { normal } else { -normal };
+
+        let record = HitRecord {
+            point,
+            shading_normal: normal.clone(),
+            tangent: (!tangent.near_zero()).then(|| tangent.normal()),
+            normal,
+            direction: ray.direction.clone(),
+            t: root,
+            front,
+            u,
+            v,
+            vertex_color: None,
+            barycentrics: None,
+            wavelengths: None,
+        };
+        Hit {
+            record,
+            material: self.material.clone(),
+        }
+    }
+}
+
+impl Hittable for Sphere {
+    fn hit(&self, ray: &Ray, interval: Interval) -> Option<Hit> {
+        let root = self.root(ray, interval)?;
+        Some(self.hit_at(ray, root))
+    }
+
+    fn occluded(&self, ray: &Ray, t_max: f64) -> bool {
+        self.root(ray, Interval::new(T_EPSILON, t_max)).is_some()
+    }
+
+    fn spans(&self, ray: &Ray) -> Vec<Span> {
+        let Some(enter) = self.root(ray, Interval::universe()) else {
+            return Vec::new();
+        };
+        match self.root(ray, Interval::new(enter, f64::INFINITY)) {
+            Some(exit) => vec![Span {
+                enter: self.hit_at(ray, enter),
+                exit: self.hit_at(ray, exit),
+            }],
+            None => Vec::new(),
+        }
+    }
+
+    fn emitting_area(&self) -> f64 {
+        if !self.material.emissive() {
+            return 0.0;
+        }
+        4.0 * PI * self.radius * self.radius
+    }
+
+    fn sample_emitting(&self) -> Option<Hit> {
+        if !self.material.emissive() {
+            return None;
+        }
+        let outward = Vector3::random_unit();
+        let ray = Ray::new(&self.center + &outward * (2.0 * self.radius), -outward);
+        Some(self.hit_at(&ray, self.radius))
+    }
+
+    fn for_each_material(&self, found: &mut dyn FnMut(&MaterialRef)) {
+        found(&self.material);
     }
 }
@@ -0,0 +1,44 @@
+use crate::{
+    component::ray::Ray,
+    math::{Interval, Vector3},
+};
+
+/// Distance and barycentric coordinates of the second and third vertices
+pub struct TriangleHit {
+    pub t: f64,
+    pub b1: f64,
+    pub b2: f64,
+}
+
+impl TriangleHit {
+    /// Interpolate a per-vertex attribute
+    pub fn interpolate(&self, values: [&Vector3; 3]) -> Vector3 {
+        values[0] * (1.0 - self.b1 - self.b2) + values[1] * self.b1 + values[2] * self.b2
+    }
+}
+
+/// Möller-Trumbore intersection
+pub fn intersect(ray: &Ray, interval: Interval, vertices: [&Vector3; 3]) -> Option<TriangleHit> {
+    let edge1 = vertices[1] - vertices[0];
+    let edge2 = vertices[2] - vertices[0];
+    let p = ray.direction.cross(&edge2);
+    let determinant = edge1.dot(&p);
+    if determinant.abs() < 1e-12 {
+        return None;
+    }
+
+    let inv = 1.0 / determinant;
+    let s = &ray.origin - vertices[0];
+    let b1 = s.dot(&p) * inv;
+    if !(0.0..=1.0).contains(&b1) {
+        return None;
+    }
+    let q = s.cross(&edge1);
+    let b2 = ray.direction.dot(&q) * inv;
+    if b2 < 0.0 || b1 + b2 > 1.0 {
+        return None;
+    }
+
+    let t = edge2.dot(&q) * inv;
+    interval.surrounds(t).then_some(TriangleHit { t, b1, b2 })
+}
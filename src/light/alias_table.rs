@@ -0,0 +1,83 @@
+use crate::math::{self, Vector3};
+
+use super::{LightBounds, LightSampler};
+
+/// Picks lights, or anything else, in proportion to their power in constant time,
+/// Vose's alias method
+pub struct AliasTable {
+    /// Chance of keeping each bin instead of taking its alias
+    keep: Vec<f64>,
+    aliases: Vec<usize>,
+    probabilities: Vec<f64>,
+}
+
+impl AliasTable {
+    /// `None` when no light emits anything
+    pub fn new(lights: &[LightBounds]) -> Option<Self> {
+        let powers: Vec<f64> = lights.iter().map(|l| l.power).collect();
+        Self::from_weights(&powers)
+    }
+
+    /// `None` when every weight is zero
+    pub fn from_weights(weights: &[f64]) -> Option<Self> {
+        let powers: Vec<f64> = weights.iter().map(|w| w.max(0.0)).collect();
+        let total: f64 = powers.iter().sum();
+        if total <= 0.0 {
+            return None;
+        }
+
+        let count = powers.len();
+        let probabilities: Vec<f64> = powers.iter().map(|p| p / total).collect();
+        let mut keep: Vec<f64> = probabilities.iter().map(|p| p * count as f64).collect();
+        let mut aliases: Vec<usize> = (0..count).collect();
+
+        let (mut small, mut large): (Vec<usize>, Vec<usize>) =
+            (0..count).partition(|&i| keep[i] < 1.0);
+        while let (Some(&less), Some(&more)) = (small.last(), large.last()) {
+            small.pop();
+            aliases[less] = more;
+            // the large bin fills up what the small one lacks
+            keep[more] -= 1.0 - keep[less];
+            if keep[more] < 1.0 {
+                large.pop();
+                small.push(more);
+            }
+        }
+        // whatever is left only misses rounding errors
+        for i in small.into_iter().chain(large) {
+            keep[i] = 1.0;
+        }
+
+        Some(Self {
+            keep,
+            aliases,
+            probabilities,
+        })
+    }
+
+    /// Index and the chance of picking it
+    pub fn sample(&self) -> (usize, f64) {
+        let scaled = math::rand_f64() * self.keep.len() as f64;
+        let bin = (scaled as usize).min(self.keep.len() - 1);
+        let index = if scaled - (bin as f64) < self.keep[bin] {
+            bin
+        } else {
+            self.aliases[bin]
+        };
+        (index, self.probabilities[index])
+    }
+
+    pub fn probability(&self, index: usize) -> f64 {
+        self.probabilities[index]
+    }
+}
+
+impl LightSampler for AliasTable {
+    fn pick(&self, _point: &Vector3) -> Option<(usize, f64)> {
+        Some(self.sample())
+    }
+
+    fn probability(&self, _point: &Vector3, index: usize) -> f64 {
+        self.probabilities[index]
+    }
+}
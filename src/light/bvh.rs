@@ -0,0 +1,139 @@
+use crate::math::{self, Vector3};
+
+use super::{LightBounds, LightSampler};
+
+enum Node {
+    Leaf(usize),
+    Inner(Box<Cluster>, Box<Cluster>),
+}
+
+/// Lights grouped by position with the bounds of their emission
+struct Cluster {
+    bounds: LightBounds,
+    node: Node,
+}
+
+impl Cluster {
+    /// Also records the turns down to every leaf, `true` for the left side
+    fn build(
+        mut lights: Vec<(usize, LightBounds)>,
+        turns: &mut Vec<bool>,
+        paths: &mut [Vec<bool>],
+    ) -> Self {
+        let bounds = lights[1..]
+            .iter()
+            .fold(lights[0].1.clone(), |total, (_, b)| total.union(b));
+
+        if lights.len() == 1 {
+            paths[lights[0].0] = turns.clone();
+            return Self {
+                bounds,
+                node: Node::Leaf(lights[0].0),
+            };
+        }
+
+        // split in half along the widest axis
+        let extent = &bounds.bounds.max - &bounds.bounds.min;
+        let axis = |b: &LightBounds| {
+            let p = b.bounds.centroid();
+            if extent.x >= extent.y && extent.x >= extent.z {
+                p.x
+            } else if extent.y >= extent.z {
+                p.y
+            } else {
+                p.z
+            }
+        };
+        lights.sort_by(|a, b| axis(&a.1).total_cmp(&axis(&b.1)));
+        let right = lights.split_off(lights.len() / 2);
+
+        turns.push(true);
+        let left = Self::build(lights, turns, paths);
+        turns.pop();
+        turns.push(false);
+        let right = Self::build(right, turns, paths);
+        turns.pop();
+        Self {
+            bounds,
+            node: Node::Inner(Box::new(left), Box::new(right)),
+        }
+    }
+
+    /// Chance of going left from an inner cluster, `None` if neither side lights the point
+    fn chance_left(left: &Self, right: &Self, point: &Vector3) -> Option<f64> {
+        let left_importance = left.bounds.importance(point);
+        let total = left_importance + right.bounds.importance(point);
+        (total > 0.0).then(|| left_importance / total)
+    }
+}
+
+/// Picks lights by their estimated contribution to the shading point, walking down a
+/// tree of light clusters and choosing the nearer, stronger or better aimed side each
+/// time
+pub struct LightBvh {
+    root: Cluster,
+    /// Turns from the root to every light, empty for the ones left out
+    paths: Vec<Vec<bool>>,
+}
+
+impl LightBvh {
+    /// `None` when no light emits anything
+    pub fn new(lights: &[LightBounds]) -> Option<Self> {
+        let emitting: Vec<(usize, LightBounds)> = lights
+            .iter()
+            .cloned()
+            .enumerate()
+            .filter(|(_, light)| light.power > 0.0)
+            .collect();
+        if emitting.is_empty() {
+            return None;
+        }
+        let mut paths = vec![Vec::new(); lights.len()];
+        let root = Cluster::build(emitting, &mut Vec::new(), &mut paths);
+        Some(Self { root, paths })
+    }
+}
+
+impl LightSampler for LightBvh {
+    fn pick(&self, point: &Vector3) -> Option<(usize, f64)> {
+        let mut cluster = &self.root;
+        let mut probability = 1.0;
+        loop {
+            match &cluster.node {
+                Node::Leaf(index) => return Some((*index, probability)),
+                Node::Inner(left, right) => {
+                    let chance = Cluster::chance_left(left, right, point)?;
+                    if math::rand_f64() < chance {
+                        probability *= chance;
+                        cluster = left;
+                    } else {
+                        probability *= 1.0 - chance;
+                        cluster = right;
+                    }
+                }
+            }
+        }
+    }
+
+    fn probability(&self, point: &Vector3, index: usize) -> f64 {
+        let mut cluster = &self.root;
+        let mut probability = 1.0;
+        for &go_left in self.paths[index].iter() {
+            let Node::Inner(left, right) = &cluster.node else {
+                break;
+            };
+            let Some(chance) = Cluster::chance_left(left, right, point) else {
+                return 0.0;
+            };
+            (probability, cluster) = if go_left {
+                (probability * chance, left)
+            } else {
+                (probability * (1.0 - chance), right)
+            };
+        }
+        match cluster.node {
+            Node::Leaf(leaf) if leaf == index => probability,
+            _ => 0.0,
+        }
+    }
+}
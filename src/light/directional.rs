@@ -0,0 +1,39 @@
+use crate::math::Vector3;
+
+use super::{Light, LightSample};
+
+/// Parallel light from infinitely far away like the sun, `irradiance` in watts per
+/// square meter on a surface facing it
+pub struct DirectionalLight {
+    /// Towards the light, against the way it travels
+    towards: Vector3,
+    irradiance: Vector3,
+}
+
+impl DirectionalLight {
+    /// `direction` is the way the light travels
+    pub fn new(direction: Vector3, irradiance: Vector3) -> Self {
+        Self {
+            towards: -direction.normal(),
+            irradiance,
+        }
+    }
+}
+
+impl Light for DirectionalLight {
+    fn sample(&self, _point: &Vector3) -> Option<LightSample> {
+        Some(LightSample {
+            direction: self.towards.clone(),
+            distance: f64::INFINITY,
+            irradiance: self.irradiance.clone(),
+        })
+    }
+
+    fn position(&self) -> Option<Vector3> {
+        None
+    }
+
+    fn power(&self) -> f64 {
+        self.irradiance.luminance()
+    }
+}
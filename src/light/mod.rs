@@ -0,0 +1,146 @@
+use std::f64::consts::PI;
+
+use crate::{geometry::aabb::Aabb, math::Vector3};
+
+pub mod alias_table;
+pub mod bvh;
+pub mod directional;
+pub mod point;
+pub mod spot;
+
+/// Light arriving at a point from one light
+pub struct LightSample {
+    /// Unit direction towards the light
+    pub direction: Vector3,
+    /// Up to the light, infinite for directional ones
+    pub distance: f64,
+    /// Radiance already integrated over the light, the irradiance on a surface facing it
+    pub irradiance: Vector3,
+}
+
+/// Light leaving a light in a random direction, to trace paths from it
+pub struct EmissionSample {
+    pub origin: Vector3,
+    /// Unit direction the light travels
+    pub direction: Vector3,
+    /// Radiant intensity along the direction
+    pub intensity: Vector3,
+    /// Density of the direction over the sphere
+    pub pdf: f64,
+}
+
+/// Where a light, or a group of them, emits from and towards, to guess its share of
+/// the light reaching a point
+#[derive(Clone)]
+pub struct LightBounds {
+    pub bounds: Aabb,
+    /// Luminance of the emitted power
+    pub power: f64,
+    /// Unit direction in the middle of the emission
+    pub axis: Vector3,
+    /// Angle around the axis every emitting direction of a surface or spot is within
+    pub spread: f64,
+    /// Angle past the spread where emission ends
+    pub falloff: f64,
+}
+
+impl LightBounds {
+    /// Emitting the same power in every direction around a region
+    pub fn uniform(bounds: Aabb, power: f64) -> Self {
+        Self {
+            bounds,
+            power,
+            axis: Vector3::new(0.0, 0.0, 1.0),
+            spread: PI,
+            falloff: PI / 2.0,
+        }
+    }
+
+    /// Both bounds together, with a cone around the two cones
+    pub fn union(&self, other: &Self) -> Self {
+        let mut bounds = self.bounds.clone();
+        bounds.grow(&other.bounds.min);
+        bounds.grow(&other.bounds.max);
+
+        let sum = &self.axis * self.power.max(1e-8) + &other.axis * other.power.max(1e-8);
+        let (axis, spread) = if self.spread >= PI || other.spread >= PI || sum.near_zero() {
+            (self.axis.clone(), PI)
+        } else {
+            let axis = sum.normal();
+            let reach = |b: &Self| axis.dot(&b.axis).clamp(-1.0, 1.0).acos() + b.spread;
+            let spread = reach(self).max(reach(other)).min(PI);
+            (axis, spread)
+        };
+        Self {
+            bounds,
+            power: self.power + other.power,
+            axis,
+            spread,
+            falloff: self.falloff.max(other.falloff),
+        }
+    }
+
+    /// Power over the squared distance, never closer than the bounds radius, zero when
+    /// the point is outside every emitting direction
+    pub fn importance(&self, point: &Vector3) -> f64 {
+        let center = self.bounds.centroid();
+        let offset = point - &center;
+        let radius_squared = (&self.bounds.max - &self.bounds.min).quadrance() / 4.0;
+        let distance_squared = offset.quadrance().max(radius_squared).max(1e-8);
+
+        if self.spread < PI {
+            // smallest angle between an emitting direction and one towards the point
+            let towards = offset.normal();
+            let angle = self.axis.dot(&towards).clamp(-1.0, 1.0).acos();
+            let subtended = if offset.quadrance() > radius_squared {
+                (radius_squared / offset.quadrance()).sqrt().asin()
+            } else {
+                PI
+            };
+            let closest = (angle - self.spread - subtended).max(0.0);
+            if closest >= self.falloff {
+                return 0.0;
+            }
+            return self.power * closest.cos().max(0.0) / distance_squared;
+        }
+        self.power / distance_squared
+    }
+}
+
+/// Lights without a surface, so rays never hit them and they are only sampled explicitly
+pub trait Light: Send + Sync + 'static {
+    fn sample(&self, point: &Vector3) -> Option<LightSample>;
+    /// `None` for lights infinitely far away
+    fn position(&self) -> Option<Vector3>;
+    /// Luminance of the emitted power, per square meter for lights without a position
+    fn power(&self) -> f64;
+    /// Only for lights with a position, uniform around it unless told otherwise
+    fn bounds(&self) -> Option<LightBounds> {
+        let position = self.position()?;
+        Some(LightBounds::uniform(
+            Aabb::new(position.clone(), position),
+            self.power(),
+        ))
+    }
+    /// Only for lights with a position
+    fn sample_emission(&self) -> Option<EmissionSample> {
+        None
+    }
+    /// Density `sample_emission` picks the unit `direction` with
+    fn pdf_emission(&self, _direction: &Vector3) -> f64 {
+        0.0
+    }
+}
+
+pub type LightRef = Box<dyn Light>;
+
+/// Chooses one light or light emitting object per shading point, for scenes with too
+/// many to sample them all
+pub trait LightSampler: Send + Sync + 'static {
+    /// Index of the light and the chance of picking it
+    fn pick(&self, point: &Vector3) -> Option<(usize, f64)>;
+    /// Chance of `pick` choosing the light at `index` for the point
+    fn probability(&self, point: &Vector3, index: usize) -> f64;
+}
+
+pub type LightSamplerRef = Box<dyn LightSampler>;
@@ -0,0 +1,56 @@
+use std::f64::consts::PI;
+
+use crate::math::Vector3;
+
+use super::{EmissionSample, Light, LightSample};
+
+/// Light from a single point in every direction, `intensity` in watts per steradian
+pub struct PointLight {
+    position: Vector3,
+    intensity: Vector3,
+}
+
+impl PointLight {
+    pub fn new(position: Vector3, intensity: Vector3) -> Self {
+        Self {
+            position,
+            intensity,
+        }
+    }
+}
+
+impl Light for PointLight {
+    fn sample(&self, point: &Vector3) -> Option<LightSample> {
+        let offset = &self.position - point;
+        let distance = offset.len();
+        if distance == 0.0 {
+            return None;
+        }
+        Some(LightSample {
+            direction: offset / distance,
+            distance,
+            irradiance: &self.intensity / (distance * distance),
+        })
+    }
+
+    fn position(&self) -> Option<Vector3> {
+        Some(self.position.clone())
+    }
+
+    fn power(&self) -> f64 {
+        4.0 * PI * self.intensity.luminance()
+    }
+
+    fn sample_emission(&self) -> Option<EmissionSample> {
+        Some(EmissionSample {
+            origin: self.position.clone(),
+            direction: Vector3::random_unit(),
+            intensity: self.intensity.clone(),
+            pdf: 1.0 / (4.0 * PI),
+        })
+    }
+
+    fn pdf_emission(&self, _direction: &Vector3) -> f64 {
+        1.0 / (4.0 * PI)
+    }
+}
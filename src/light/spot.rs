@@ -0,0 +1,155 @@
+use std::f64::consts::PI;
+
+use crate::math::{self, Vector3};
+
+use crate::geometry::aabb::Aabb;
+
+use super::{EmissionSample, Light, LightBounds, LightSample};
+
+/// Steps integrating the falloff and profile over the cone for the solid angle
+const POWER_STEPS: usize = 64;
+
+/// Point light limited to a cone around `direction`, fading out between the inner and
+/// outer angles. An optional `profile` scales the intensity at evenly spaced angles from
+/// the axis to the outer angle, like a goniometric (IES) measurement.
+pub struct SpotLight {
+    position: Vector3,
+    direction: Vector3,
+    intensity: Vector3,
+    cos_inner: f64,
+    cos_outer: f64,
+    outer_angle: f64,
+    profile: Option<Vec<f64>>,
+    /// Solid angle of the cone weighted by the falloff and the profile
+    solid_angle: f64,
+}
+
+impl SpotLight {
+    /// Angles in radians
+    pub fn new(
+        position: Vector3,
+        direction: Vector3,
+        intensity: Vector3,
+        inner_angle: f64,
+        outer_angle: f64,
+        profile: Option<Vec<f64>>,
+    ) -> Self {
+        let inner_angle = inner_angle.min(outer_angle);
+        let mut light = Self {
+            position,
+            direction: direction.normal(),
+            intensity,
+            cos_inner: inner_angle.cos(),
+            cos_outer: outer_angle.cos(),
+            outer_angle,
+            profile: profile.filter(|p| !p.is_empty()),
+            solid_angle: 0.0,
+        };
+        // falloff over the solid angle, in steps of the cosine
+        let step = (1.0 - light.cos_outer) / POWER_STEPS as f64;
+        let falloff: f64 = (0..POWER_STEPS)
+            .map(|i| light.falloff(light.cos_outer + (i as f64 + 0.5) * step))
+            .sum();
+        light.solid_angle = 2.0 * PI * falloff * step;
+        light
+    }
+
+    /// Steradians the intensity effectively covers, its power is the intensity times this
+    pub fn solid_angle(&self) -> f64 {
+        self.solid_angle
+    }
+
+    /// Same light with the intensity multiplied by `factor`
+    pub fn scaled(mut self, factor: f64) -> Self {
+        self.intensity = &self.intensity * factor;
+        self
+    }
+
+    fn falloff(&self, cos_theta: f64) -> f64 {
+        if cos_theta <= self.cos_outer {
+            return 0.0;
+        }
+        let fade = if cos_theta >= self.cos_inner {
+            1.0
+        } else {
+            let x = (cos_theta - self.cos_outer) / (self.cos_inner - self.cos_outer);
+            x * x * (3.0 - 2.0 * x)
+        };
+
+        let measured = match &self.profile {
+            Some(profile) if profile.len() > 1 => {
+                let position = cos_theta.clamp(-1.0, 1.0).acos() / self.outer_angle
+                    * (profile.len() - 1) as f64;
+                let index = (position as usize).min(profile.len() - 2);
+                let fraction = position - index as f64;
+                profile[index] * (1.0 - fraction) + profile[index + 1] * fraction
+            }
+            Some(profile) => profile[0],
+            None => 1.0,
+        };
+        fade * measured
+    }
+}
+
+impl Light for SpotLight {
+    fn sample(&self, point: &Vector3) -> Option<LightSample> {
+        let offset = &self.position - point;
+        let distance = offset.len();
+        if distance == 0.0 {
+            return None;
+        }
+        let direction = offset / distance;
+
+        let falloff = self.falloff(-direction.dot(&self.direction));
+        if falloff <= 0.0 {
+            return None;
+        }
+        Some(LightSample {
+            direction,
+            distance,
+            irradiance: &self.intensity * (falloff / (distance * distance)),
+        })
+    }
+
+    fn position(&self) -> Option<Vector3> {
+        Some(self.position.clone())
+    }
+
+    fn power(&self) -> f64 {
+        self.solid_angle * self.intensity.luminance()
+    }
+
+    fn bounds(&self) -> Option<LightBounds> {
+        Some(LightBounds {
+            bounds: Aabb::new(self.position.clone(), self.position.clone()),
+            power: self.power(),
+            axis: self.direction.clone(),
+            spread: 0.0,
+            falloff: self.outer_angle,
+        })
+    }
+
+    /// Uniform over the outer cone
+    fn sample_emission(&self) -> Option<EmissionSample> {
+        let cos_theta = 1.0 - math::rand_f64() * (1.0 - self.cos_outer);
+        let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+        let phi = 2.0 * PI * math::rand_f64();
+        let (tangent, bitangent) = self.direction.orthonormal_basis();
+        let direction = tangent * (sin_theta * phi.cos())
+            + bitangent * (sin_theta * phi.sin())
+            + &self.direction * cos_theta;
+        Some(EmissionSample {
+            origin: self.position.clone(),
+            direction,
+            intensity: &self.intensity * self.falloff(cos_theta),
+            pdf: 1.0 / (2.0 * PI * (1.0 - self.cos_outer)),
+        })
+    }
+
+    fn pdf_emission(&self, direction: &Vector3) -> f64 {
+        if direction.dot(&self.direction) <= self.cos_outer {
+            return 0.0;
+        }
+        1.0 / (2.0 * PI * (1.0 - self.cos_outer))
+    }
+}
@@ -1,9 +1,14 @@
+mod background;
 mod caster;
 mod component;
+mod description;
 mod geometry;
+mod light;
 mod material;
 mod math;
 mod settings;
+mod spectrum;
+mod texture;
 
 fn main() {
     let caster = caster::Caster::build().unwrap();
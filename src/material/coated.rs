@@ -0,0 +1,125 @@
+use crate::{
+    component::{hit::HitRecord, ray::Ray},
+    math::{self, Vector3},
+};
+
+use super::{
+    microfacet::{Frame, Ggx},
+    Material, MaterialRef, ScatterResult,
+};
+
+/// Dielectric layer over another material, like varnish or lacquer. Light either
+/// reflects off the layer or goes through it twice, tinted by `color` once per
+/// unit of distance across it, to scatter off the `base`.
+pub struct Coated {
+    base: MaterialRef,
+    refraction_index: f64,
+    distribution: Ggx,
+    color: Vector3,
+}
+
+impl Coated {
+    pub fn new(base: MaterialRef, refraction_index: f64, roughness: f64, color: Vector3) -> Self {
+        Self {
+            base,
+            refraction_index,
+            distribution: Ggx::new(roughness, 0.0),
+            color,
+        }
+    }
+}
+
+impl Coated {
+    fn fresnel(&self, cosine: f64) -> f64 {
+        let f0 = ((self.refraction_index - 1.0) / (self.refraction_index + 1.0)).powi(2);
+        f0 + (1.0 - f0) * (1.0 - cosine.clamp(0.0, 1.0)).powi(5)
+    }
+
+    /// Longer paths across the layer at grazing angles, the layer being one unit thick
+    fn tint(&self, cos_in: f64, cos_out: f64) -> Vector3 {
+        let distance = 1.0 / cos_in.max(1e-3) + 1.0 / cos_out.max(1e-3);
+        Vector3::new(
+            self.color.x.powf(distance),
+            self.color.y.powf(distance),
+            self.color.z.powf(distance),
+        )
+    }
+}
+
+impl Material for Coated {
+    fn scatter(&self, record: HitRecord) -> Option<ScatterResult> {
+        let frame = Frame::new(&record);
+        let wo = frame.to_local(&-record.direction.normal());
+
+        if wo.z > 0.0 && math::rand_f64() < self.fresnel(wo.z) {
+            let (direction, _, weight) =
+                self.distribution
+                    .sample_reflection(&frame, &wo, &record.normal)?;
+            return Some(ScatterResult {
+                ray: Ray::new(record.point.clone(), direction),
+                attenuation: Vector3::fill(1.0) * weight,
+                dispersed: false,
+            });
+        }
+
+        let normal = record.shading_normal.clone();
+        let scattered = self.base.scatter(record)?;
+        let cos_out = scattered.ray.direction.normal().dot(&normal);
+        Some(ScatterResult {
+            attenuation: scattered.attenuation * self.tint(wo.z, cos_out),
+            ..scattered
+        })
+    }
+
+    fn eval(&self, record: &HitRecord, direction: &Vector3) -> Option<Vector3> {
+        let frame = Frame::new(record);
+        let wo = frame.to_local(&-record.direction.normal());
+        let wi = frame.to_local(direction);
+        let fresnel = if wo.z > 0.0 { self.fresnel(wo.z) } else { 0.0 };
+
+        let coat = if direction.dot(&record.normal) > 0.0 {
+            fresnel * self.distribution.eval_reflection(&wo, &wi)
+        } else {
+            0.0
+        };
+        let base = self
+            .base
+            .eval(record, direction)
+            .map(|base| base * self.tint(wo.z, wi.z) * (1.0 - fresnel));
+        match base {
+            Some(base) => Some(base + coat),
+            None => (coat > 0.0).then(|| Vector3::fill(coat)),
+        }
+    }
+
+    /// Mirror-like wherever the base is
+    fn is_delta(&self, record: &HitRecord) -> bool {
+        self.base.is_delta(record)
+    }
+
+    /// `None` over bases with mirror-like lobes
+    fn pdf(&self, record: &HitRecord, direction: &Vector3) -> Option<f64> {
+        let base = self.base.pdf(record, direction)?;
+        let frame = Frame::new(record);
+        let wo = frame.to_local(&-record.direction.normal());
+        if wo.z <= 0.0 {
+            return Some(base);
+        }
+        let fresnel = self.fresnel(wo.z);
+        let coat = if direction.dot(&record.normal) > 0.0 {
+            self.distribution
+                .pdf_reflection(&wo, &frame.to_local(direction))
+        } else {
+            0.0
+        };
+        Some(fresnel * coat + (1.0 - fresnel) * base)
+    }
+
+    fn emit(&self, record: &HitRecord) -> Vector3 {
+        self.base.emit(record)
+    }
+
+    fn emissive(&self) -> bool {
+        self.base.emissive()
+    }
+}
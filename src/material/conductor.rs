@@ -0,0 +1,154 @@
+use serde::Deserialize;
+
+use crate::{
+    component::{hit::HitRecord, ray::Ray},
+    math::Vector3,
+};
+
+use super::{
+    microfacet::{Frame, Ggx},
+    thin_film::{Substrate, ThinFilm},
+    Material, ScatterResult,
+};
+
+/// Measured complex refractive indices at the red, green and blue wavelengths
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConductorPreset {
+    Gold,
+    Copper,
+    Aluminium,
+    Silver,
+}
+
+impl ConductorPreset {
+    /// `(eta, k)`
+    pub fn ior(self) -> (Vector3, Vector3) {
+        let (eta, k) = match self {
+            Self::Gold => ([0.143, 0.374, 1.442], [3.983, 2.385, 1.603]),
+            Self::Copper => ([0.200, 0.924, 1.102], [3.912, 2.452, 2.142]),
+            Self::Aluminium => ([1.657, 0.880, 0.521], [9.224, 6.270, 4.837]),
+            Self::Silver => ([0.155, 0.117, 0.138], [4.828, 3.122, 2.147]),
+        };
+        (
+            Vector3::new(eta[0], eta[1], eta[2]),
+            Vector3::new(k[0], k[1], k[2]),
+        )
+    }
+}
+
+/// Rough metal, GGX microfacets with the exact Fresnel term of a complex index of refraction
+pub struct Conductor {
+    eta: Vector3,
+    k: Vector3,
+    distribution: Ggx,
+    thin_film: Option<ThinFilm>,
+}
+
+impl Conductor {
+    pub fn new(
+        eta: Vector3,
+        k: Vector3,
+        roughness: f64,
+        anisotropy: f64,
+        thin_film: Option<ThinFilm>,
+    ) -> Self {
+        Self {
+            eta,
+            k,
+            distribution: Ggx::new(roughness, anisotropy),
+            thin_film,
+        }
+    }
+
+    /// Exact Fresnel reflectance of one channel
+    pub fn fresnel(cos_theta: f64, eta: f64, k: f64) -> f64 {
+        let cos2 = cos_theta * cos_theta;
+        let sin2 = 1.0 - cos2;
+        let t0 = eta * eta - k * k - sin2;
+        let a2_plus_b2 = (t0 * t0 + 4.0 * eta * eta * k * k).sqrt();
+        let a = (0.5 * (a2_plus_b2 + t0)).max(0.0).sqrt();
+
+        let t1 = a2_plus_b2 + cos2;
+        let t2 = 2.0 * cos_theta * a;
+        let rs = (t1 - t2) / (t1 + t2);
+
+        let t3 = cos2 * a2_plus_b2 + sin2 * sin2;
+        let t4 = t2 * sin2;
+        let rp = rs * (t3 - t4) / (t3 + t4);
+        0.5 * (rs + rp)
+    }
+}
+
+impl Conductor {
+    fn reflectance(&self, cos_theta: f64) -> Vector3 {
+        match &self.thin_film {
+            Some(film) => film.reflectance(
+                cos_theta,
+                1.0,
+                Substrate::Conductor {
+                    eta: &self.eta,
+                    k: &self.k,
+                },
+            ),
+            None => Vector3::new(
+                Self::fresnel(cos_theta, self.eta.x, self.k.x),
+                Self::fresnel(cos_theta, self.eta.y, self.k.y),
+                Self::fresnel(cos_theta, self.eta.z, self.k.z),
+            ),
+        }
+    }
+}
+
+impl Material for Conductor {
+    fn eval(&self, record: &HitRecord, direction: &Vector3) -> Option<Vector3> {
+        if self.distribution.is_smooth() || direction.dot(&record.normal) <= 0.0 {
+            return None;
+        }
+        let frame = Frame::new(record);
+        let wo = frame.to_local(&-record.direction.normal());
+        let wi = frame.to_local(direction);
+        let value = self.distribution.eval_reflection(&wo, &wi);
+        if value <= 0.0 {
+            return None;
+        }
+        let cos_theta = wo.dot(&(&wo + &wi).normal()).clamp(0.0, 1.0);
+        Some(self.reflectance(cos_theta) * value)
+    }
+
+    /// Polished metal only reflects the mirror direction
+    fn is_delta(&self, _record: &HitRecord) -> bool {
+        self.distribution.is_smooth()
+    }
+
+    fn pdf(&self, record: &HitRecord, direction: &Vector3) -> Option<f64> {
+        if self.distribution.is_smooth() {
+            return None;
+        }
+        let frame = Frame::new(record);
+        let wo = frame.to_local(&-record.direction.normal());
+        Some(
+            self.distribution
+                .pdf_reflection(&wo, &frame.to_local(direction)),
+        )
+    }
+
+    fn scatter(&self, record: HitRecord) -> Option<ScatterResult> {
+        let frame = Frame::new(&record);
+        let wo = frame.to_local(&-record.direction.normal());
+        if wo.z <= 0.0 {
+            return None;
+        }
+
+        let (direction, cos_theta, weight) =
+            self.distribution
+                .sample_reflection(&frame, &wo, &record.normal)?;
+        let cos_theta = cos_theta.clamp(0.0, 1.0);
+        let fresnel = self.reflectance(cos_theta);
+        Some(ScatterResult {
+            ray: Ray::new(record.point.clone(), direction),
+            attenuation: fresnel * weight,
+            dispersed: false,
+        })
+    }
+}
@@ -1,19 +1,45 @@
-use crate::math::Vector3;
+use std::sync::Arc;
+
+use crate::{
+    component::hit::HitRecord,
+    math::Vector3,
+    texture::{solid_color::SolidColor, TextureRef},
+};
 
 use super::Material;
 
 pub struct DiffuseLight {
-    color: Vector3,
+    emission: TextureRef,
+    /// Radiance scale, times the blackbody color when a temperature is given
+    intensity: Vector3,
+    /// One-sided lights only emit from the side the normal points to
+    two_sided: bool,
 }
 
 impl DiffuseLight {
+    /// Constant color from every side
     pub fn new(color: Vector3) -> Self {
-        Self { color }
+        Self::textured(Arc::new(SolidColor::new(color)), Vector3::fill(1.0), true)
+    }
+
+    pub fn textured(emission: TextureRef, intensity: Vector3, two_sided: bool) -> Self {
+        Self {
+            emission,
+            intensity,
+            two_sided,
+        }
     }
 }
 
 impl Material for DiffuseLight {
-    fn emit(&self) -> Vector3 {
-        self.color.clone()
+    fn emit(&self, record: &HitRecord) -> Vector3 {
+        if !self.two_sided && !record.front {
+            return Vector3::fill(0.0);
+        }
+        self.emission.value(record) * &self.intensity
+    }
+
+    fn emissive(&self) -> bool {
+        true
     }
 }
@@ -1,19 +1,73 @@
 use rand::Rng;
+use serde::Deserialize;
 
 use crate::{
     component::{hit::HitRecord, ray::Ray},
     math::Vector3,
 };
 
-use super::{Material, ScatterResult};
+use super::{
+    microfacet::{Frame, Ggx},
+    thin_film::{Substrate, ThinFilm},
+    Material, ScatterResult,
+};
+
+/// Index of refraction as a function of the wavelength, in micrometers in the formulas
+#[derive(Clone, Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Dispersion {
+    /// `n = a + b / λ²`
+    Cauchy { a: f64, b: f64 },
+    /// `n² = 1 + Σ bᵢ λ² / (λ² - cᵢ)`
+    Sellmeier { b: [f64; 3], c: [f64; 3] },
+}
+
+impl Dispersion {
+    /// Index at a wavelength in nanometers
+    pub fn index(&self, wavelength: f64) -> f64 {
+        let micrometers = wavelength / 1000.0;
+        let squared = micrometers * micrometers;
+        match self {
+            Self::Cauchy { a, b } => a + b / squared,
+            Self::Sellmeier { b, c } => {
+                let sum: f64 = (0..3).map(|i| b[i] * squared / (squared - c[i])).sum();
+                (1.0 + sum).sqrt()
+            }
+        }
+    }
+}
 
 pub struct Glass {
+    /// Used when not tracing spectrally or without dispersion
     refraction_index: f64,
+    dispersion: Option<Dispersion>,
+    /// Microfacet roughness, `None` for a perfectly smooth surface
+    distribution: Option<Ggx>,
+    /// Absorption coefficient of the interior per unit of length
+    absorption: Vector3,
+    /// Coating on the outside, a soap bubble is a film over glass of index 1
+    thin_film: Option<ThinFilm>,
 }
 
 impl Glass {
     pub fn new(refraction_index: f64) -> Self {
-        Self { refraction_index }
+        Self::with_parameters(refraction_index, 0.0, Vector3::fill(0.0), None, None)
+    }
+
+    pub fn with_parameters(
+        refraction_index: f64,
+        roughness: f64,
+        absorption: Vector3,
+        dispersion: Option<Dispersion>,
+        thin_film: Option<ThinFilm>,
+    ) -> Self {
+        Self {
+            refraction_index,
+            dispersion,
+            distribution: (roughness > 0.0).then(|| Ggx::new(roughness, 0.0)),
+            absorption,
+            thin_film,
+        }
     }
 
     fn reflectance(cosine: f64, refraction_index: f64) -> f64 {
@@ -21,31 +75,101 @@ impl Glass {
         let r0 = r0 * r0;
         r0 + (1.0 - r0) * (1.0 - cosine).powi(5)
     }
+
+    /// Beer-Lambert falloff over the path inside, which ends at hits from the back
+    fn transmittance(&self, record: &HitRecord) -> Vector3 {
+        if record.front {
+            return Vector3::fill(1.0);
+        }
+        let distance = record.t * record.direction.len();
+        let optical_depth = &self.absorption * -distance;
+        Vector3::new(
+            optical_depth.x.exp(),
+            optical_depth.y.exp(),
+            optical_depth.z.exp(),
+        )
+    }
 }
 
 impl Material for Glass {
+    fn is_delta(&self, _record: &HitRecord) -> bool {
+        true
+    }
+
     fn scatter(&self, record: HitRecord) -> Option<ScatterResult> {
+        // the other wavelengths would refract elsewhere, only the hero one carries on
+        let (refraction_index, dispersed) = match (&self.dispersion, record.wavelengths) {
+            (Some(dispersion), Some(wavelengths)) => (dispersion.index(wavelengths[0]), true),
+            _ => (self.refraction_index, false),
+        };
         let refraction_ratio = if record.front {
-            1.0 / self.refraction_index
+            1.0 / refraction_index
         } else {
-            self.refraction_index
+            refraction_index
         };
         let unit_direction = record.direction.normal();
-        let cos_theta = -unit_direction.dot(&record.normal).min(1.0);
-        let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
-
-        let mut rng = rand::rng();
-        let direction = if refraction_ratio * sin_theta > 1.0
-            || Self::reflectance(cos_theta, refraction_ratio) > rng.random::<f64>()
-        {
-            unit_direction.reflect(&record.normal)
-        } else {
-            unit_direction.refract(&record.normal, refraction_ratio, cos_theta)
+        let sample = rand::rng().random::<f64>();
+        let interact = |normal: &Vector3| {
+            let cos_theta = (-unit_direction.dot(normal)).clamp(-1.0, 1.0);
+            let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
+            if refraction_ratio * sin_theta > 1.0 {
+                return (unit_direction.reflect(normal), true, Vector3::fill(1.0));
+            }
+
+            // a colored film reflectance is sampled by its average and weighted back
+            let (reflectance, film) = match &self.thin_film {
+                Some(film) => {
+                    let (outside, inside) = if record.front {
+                        (1.0, refraction_index)
+                    } else {
+                        (refraction_index, 1.0)
+                    };
+                    let film = film.reflectance(cos_theta, outside, Substrate::Dielectric(inside));
+                    ((film.x + film.y + film.z) / 3.0, Some(film))
+                }
+                None => (Self::reflectance(cos_theta, refraction_ratio), None),
+            };
+            if reflectance > sample {
+                let weight = film.map_or(Vector3::fill(1.0), |film| film / reflectance);
+                (unit_direction.reflect(normal), true, weight)
+            } else {
+                let weight = film.map_or(Vector3::fill(1.0), |film| {
+                    (Vector3::fill(1.0) - film) / (1.0 - reflectance)
+                });
+                (
+                    unit_direction.refract(normal, refraction_ratio, cos_theta),
+                    false,
+                    weight,
+                )
+            }
+        };
+
+        // rough surfaces reflect and refract about a visible microfacet normal
+        let (mut direction, reflected, mut weight) = match &self.distribution {
+            Some(distribution) => {
+                let frame = Frame::new(&record);
+                let wo = frame.to_local(&-&unit_direction);
+                let microfacet = frame.to_world(&distribution.sample_visible_normal(&wo));
+                let (direction, reflected, film) = interact(&microfacet);
+                let wi = frame.to_local(&direction.normal());
+                (
+                    direction,
+                    reflected,
+                    film * distribution.sample_weight(&wo, &wi),
+                )
+            }
+            None => interact(&record.shading_normal),
         };
+
+        // fall back to the geometric normal when the shading one sends the ray
+        // to the wrong side of the surface
+        if (direction.dot(&record.normal) > 0.0) != reflected {
+            (direction, _, weight) = interact(&record.normal);
+        }
         Some(ScatterResult {
-            _t: record.t,
             ray: Ray::new(record.point.clone(), direction),
-            attenuation: Vector3::fill(1.0),
+            attenuation: self.transmittance(&record) * weight,
+            dispersed,
         })
     }
 }
@@ -0,0 +1,74 @@
+use std::f64::consts::PI;
+
+use crate::{
+    component::{hit::HitRecord, ray::Ray},
+    math::{self, Vector3},
+};
+
+use super::{Material, ScatterResult};
+
+/// Anisotropic phase function, `g` > 0 scatters forward and `g` < 0 backward
+pub struct HenyeyGreenstein {
+    albedo: Vector3,
+    g: f64,
+}
+
+impl HenyeyGreenstein {
+    pub fn new(albedo: Vector3, g: f64) -> Self {
+        Self {
+            albedo,
+            g: g.clamp(-0.99, 0.99),
+        }
+    }
+
+    fn phase(&self, record: &HitRecord, direction: &Vector3) -> f64 {
+        let cos_theta = record.direction.normal().dot(direction);
+        let g2 = self.g * self.g;
+        let denominator = 1.0 + g2 - 2.0 * self.g * cos_theta;
+        (1.0 - g2) / (4.0 * PI * denominator * denominator.sqrt())
+    }
+
+    fn sample_cos_theta(&self) -> f64 {
+        let xi = math::rand_f64();
+        if self.g.abs() < 1e-3 {
+            return 1.0 - 2.0 * xi;
+        }
+
+        let g2 = self.g * self.g;
+        let s = (1.0 - g2) / (1.0 - self.g + 2.0 * self.g * xi);
+        ((1.0 + g2 - s * s) / (2.0 * self.g)).clamp(-1.0, 1.0)
+    }
+}
+
+impl Material for HenyeyGreenstein {
+    fn eval(&self, record: &HitRecord, direction: &Vector3) -> Option<Vector3> {
+        Some(&self.albedo * self.phase(record, direction))
+    }
+
+    fn pdf(&self, record: &HitRecord, direction: &Vector3) -> Option<f64> {
+        Some(self.phase(record, direction))
+    }
+
+    fn volumetric(&self) -> bool {
+        true
+    }
+
+    fn scatter(&self, record: HitRecord) -> Option<ScatterResult> {
+        let forward = record.direction.normal();
+        let (tangent, bitangent) = forward.orthonormal_basis();
+
+        let cos_theta = self.sample_cos_theta();
+        let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+        let phi = 2.0 * PI * math::rand_f64();
+
+        let direction = tangent * (sin_theta * phi.cos())
+            + bitangent * (sin_theta * phi.sin())
+            + forward * cos_theta;
+
+        Some(ScatterResult {
+            ray: Ray::new(record.point.clone(), direction),
+            attenuation: self.albedo.clone(),
+            dispersed: false,
+        })
+    }
+}
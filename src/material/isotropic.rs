@@ -0,0 +1,41 @@
+use std::f64::consts::PI;
+
+use crate::{
+    component::{hit::HitRecord, ray::Ray},
+    math::Vector3,
+};
+
+use super::{Material, ScatterResult};
+
+pub struct Isotropic {
+    albedo: Vector3,
+}
+
+impl Isotropic {
+    pub fn new(albedo: Vector3) -> Self {
+        Self { albedo }
+    }
+}
+
+impl Material for Isotropic {
+    fn eval(&self, _record: &HitRecord, _direction: &Vector3) -> Option<Vector3> {
+        Some(&self.albedo / (4.0 * PI))
+    }
+
+    fn pdf(&self, _record: &HitRecord, _direction: &Vector3) -> Option<f64> {
+        Some(1.0 / (4.0 * PI))
+    }
+
+    fn volumetric(&self) -> bool {
+        true
+    }
+
+    fn scatter(&self, record: HitRecord) -> Option<ScatterResult> {
+        let scattered = Ray::new(record.point.clone(), Vector3::random_unit());
+        Some(ScatterResult {
+            ray: scattered,
+            attenuation: self.albedo.clone(),
+            dispersed: false,
+        })
+    }
+}
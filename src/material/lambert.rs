@@ -1,32 +1,83 @@
+use std::{f64::consts::PI, sync::Arc};
+
 use crate::{
     component::{hit::HitRecord, ray::Ray},
     math::Vector3,
+    texture::{solid_color::SolidColor, TextureRef},
 };
 
-use super::{Material, ScatterResult};
+use super::{sheen::Sheen, Material, ScatterResult};
 
 pub struct Lambert {
-    albedo: Vector3,
+    albedo: TextureRef,
+    /// Extra lobe for cloth
+    sheen: Option<Sheen>,
 }
 
 impl Lambert {
     pub fn new(albedo: Vector3) -> Self {
-        Self { albedo }
+        Self::textured(Arc::new(SolidColor::new(albedo)))
+    }
+
+    pub fn textured(albedo: TextureRef) -> Self {
+        Self::with_sheen(albedo, None)
+    }
+
+    pub fn with_sheen(albedo: TextureRef, sheen: Option<Sheen>) -> Self {
+        Self { albedo, sheen }
     }
 }
 
 impl Material for Lambert {
+    fn eval(&self, record: &HitRecord, direction: &Vector3) -> Option<Vector3> {
+        let cosine = direction.dot(&record.shading_normal);
+        if cosine <= 0.0 || direction.dot(&record.normal) <= 0.0 {
+            return None;
+        }
+        let mut albedo = self.albedo.value(record);
+        if let Some(sheen) = &self.sheen {
+            let wo = -record.direction.normal();
+            albedo = sheen.layer(
+                albedo,
+                &Vector3::fill(1.0),
+                &wo,
+                direction,
+                &record.shading_normal,
+            );
+        }
+        Some(albedo * (cosine / PI))
+    }
+
+    fn pdf(&self, record: &HitRecord, direction: &Vector3) -> Option<f64> {
+        Some(direction.dot(&record.shading_normal).max(0.0) / PI)
+    }
+
     fn scatter(&self, record: HitRecord) -> Option<ScatterResult> {
-        let mut scatter_direction = &record.normal + Vector3::random_unit();
+        let mut scatter_direction = &record.shading_normal + Vector3::random_unit();
         if scatter_direction.near_zero() {
-            scatter_direction = record.normal.clone();
+            scatter_direction = record.shading_normal.clone();
+        }
+        // a bent shading normal can send the ray into the surface, mirror it back out
+        if scatter_direction.dot(&record.normal) <= 0.0 {
+            scatter_direction = scatter_direction.reflect(&record.normal);
         }
 
+        let mut attenuation = self.albedo.value(&record);
+        if let Some(sheen) = &self.sheen {
+            let wo = -record.direction.normal();
+            attenuation = sheen.layer(
+                attenuation,
+                &Vector3::fill(1.0),
+                &wo,
+                &scatter_direction.normal(),
+                &record.shading_normal,
+            );
+        }
         let scattered = Ray::new(record.point.clone(), scatter_direction);
         Some(ScatterResult {
-            _t: record.t,
             ray: scattered,
-            attenuation: self.albedo.clone(),
+            attenuation,
+            dispersed: false,
         })
     }
 }
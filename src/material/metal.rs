@@ -20,17 +20,23 @@ impl Metal {
 }
 
 impl Material for Metal {
+    fn is_delta(&self, _record: &HitRecord) -> bool {
+        true
+    }
+
     fn scatter(&self, record: HitRecord) -> Option<ScatterResult> {
-        let reflected = record.direction.normal().reflect(&record.normal);
+        let reflected = record.direction.normal().reflect(&record.shading_normal);
+        let direction = reflected + Vector3::random_unit() * self.fuzz;
+        // absorbed when the shading normal or fuzz points it below the surface
+        if direction.dot(&record.normal) <= 0.0 {
+            return None;
+        }
 
-        let scattered = Ray::new(
-            record.point.clone(),
-            reflected + Vector3::random_unit() * self.fuzz,
-        );
+        let scattered = Ray::new(record.point.clone(), direction);
         Some(ScatterResult {
-            _t: record.t,
             ray: scattered,
             attenuation: self.albedo.clone(),
+            dispersed: false,
         })
     }
 }
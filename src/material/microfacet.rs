@@ -0,0 +1,168 @@
+use std::f64::consts::PI;
+
+use crate::{
+    component::hit::HitRecord,
+    math::{self, Vector3},
+};
+
+/// Orthonormal frame around the shading normal, microfacet math happens with the normal as z
+pub struct Frame {
+    tangent: Vector3,
+    bitangent: Vector3,
+    normal: Vector3,
+}
+
+impl Frame {
+    /// The tangent follows the surface's `u` direction where it has one, so anisotropic
+    /// highlights line up with the parametrization
+    pub fn new(record: &HitRecord) -> Self {
+        let normal = record.shading_normal.normal();
+        let tangent = record
+            .tangent
+            .as_ref()
+            .map(|tangent| tangent - &normal * normal.dot(tangent))
+            .filter(|tangent| !tangent.near_zero());
+        let (tangent, bitangent) = match tangent {
+            Some(tangent) => {
+                let tangent = tangent.normal();
+                let bitangent = normal.cross(&tangent);
+                (tangent, bitangent)
+            }
+            None => normal.orthonormal_basis(),
+        };
+        Self {
+            tangent,
+            bitangent,
+            normal,
+        }
+    }
+
+    pub fn to_local(&self, v: &Vector3) -> Vector3 {
+        Vector3::new(
+            v.dot(&self.tangent),
+            v.dot(&self.bitangent),
+            v.dot(&self.normal),
+        )
+    }
+
+    pub fn to_world(&self, v: &Vector3) -> Vector3 {
+        &self.tangent * v.x + &self.bitangent * v.y + &self.normal * v.z
+    }
+}
+
+/// Narrowest distribution width, smoother surfaces are mirrors
+const MIN_ALPHA: f64 = 1e-4;
+
+/// Trowbridge-Reitz (GGX) distribution of microfacet normals, stretched by a
+/// different roughness along the tangent and the bitangent
+pub struct Ggx {
+    alpha_x: f64,
+    alpha_y: f64,
+}
+
+impl Ggx {
+    /// `roughness` in `0..=1` is squared into the distribution width,
+    /// `anisotropy` in `0..1` stretches the highlight along the tangent
+    pub fn new(roughness: f64, anisotropy: f64) -> Self {
+        let alpha = roughness.clamp(0.0, 1.0).powi(2);
+        let aspect = (1.0 - 0.9 * anisotropy.clamp(0.0, 1.0)).sqrt();
+        Self {
+            alpha_x: (alpha / aspect).max(MIN_ALPHA),
+            alpha_y: (alpha * aspect).max(MIN_ALPHA),
+        }
+    }
+
+    /// Both widths at the floor, the highlight is a mirror reflection
+    pub fn is_smooth(&self) -> bool {
+        self.alpha_x <= MIN_ALPHA && self.alpha_y <= MIN_ALPHA
+    }
+
+    /// Microfacet normal seen from the local direction `wo`, Heitz 2018
+    pub fn sample_visible_normal(&self, wo: &Vector3) -> Vector3 {
+        let stretched = Vector3::new(self.alpha_x * wo.x, self.alpha_y * wo.y, wo.z).normal();
+
+        let length_squared = stretched.x * stretched.x + stretched.y * stretched.y;
+        let t1 = if length_squared > 0.0 {
+            Vector3::new(-stretched.y, stretched.x, 0.0) / length_squared.sqrt()
+        } else {
+            Vector3::new(1.0, 0.0, 0.0)
+        };
+        let t2 = stretched.cross(&t1);
+
+        let r = math::rand_f64().sqrt();
+        let phi = 2.0 * PI * math::rand_f64();
+        let p1 = r * phi.cos();
+        let s = 0.5 * (1.0 + stretched.z);
+        let p2 = (1.0 - s) * (1.0 - p1 * p1).sqrt() + s * r * phi.sin();
+        let p3 = (1.0 - p1 * p1 - p2 * p2).max(0.0).sqrt();
+
+        let normal = t1 * p1 + t2 * p2 + stretched * p3;
+        Vector3::new(
+            self.alpha_x * normal.x,
+            self.alpha_y * normal.y,
+            normal.z.max(0.0),
+        )
+        .normal()
+    }
+
+    /// Density of microfacet normals
+    fn distribution(&self, h: &Vector3) -> f64 {
+        let x = h.x / self.alpha_x;
+        let y = h.y / self.alpha_y;
+        let denominator = x * x + y * y + h.z * h.z;
+        1.0 / (PI * self.alpha_x * self.alpha_y * denominator * denominator)
+    }
+
+    /// Reflection BRDF without the Fresnel term, times the cosine of `wi`
+    pub fn eval_reflection(&self, wo: &Vector3, wi: &Vector3) -> f64 {
+        if wo.z <= 0.0 || wi.z <= 0.0 {
+            return 0.0;
+        }
+        let half = (wo + wi).normal();
+        let masking = 1.0 / (1.0 + self.lambda(wo) + self.lambda(wi));
+        self.distribution(&half) * masking / (4.0 * wo.z)
+    }
+
+    /// Density of `sample_reflection` picking the local direction `wi`
+    pub fn pdf_reflection(&self, wo: &Vector3, wi: &Vector3) -> f64 {
+        if wo.z <= 0.0 || wi.z <= 0.0 {
+            return 0.0;
+        }
+        let half = (wo + wi).normal();
+        self.distribution(&half) / ((1.0 + self.lambda(wo)) * 4.0 * wo.z)
+    }
+
+    fn lambda(&self, w: &Vector3) -> f64 {
+        let cos2 = w.z * w.z;
+        if cos2 == 0.0 {
+            return f64::INFINITY;
+        }
+        let projected =
+            self.alpha_x * self.alpha_x * w.x * w.x + self.alpha_y * self.alpha_y * w.y * w.y;
+        0.5 * (-1.0 + (1.0 + projected / cos2).sqrt())
+    }
+
+    /// Mirror `wo` about a visible microfacet normal, giving the world direction,
+    /// the cosine between `wo` and the microfacet and the sample weight.
+    /// `None` when it ends up below the shading or the `geometric` surface.
+    pub fn sample_reflection(
+        &self,
+        frame: &Frame,
+        wo: &Vector3,
+        geometric: &Vector3,
+    ) -> Option<(Vector3, f64, f64)> {
+        let microfacet = self.sample_visible_normal(wo);
+        let wi = microfacet.clone() * (2.0 * wo.dot(&microfacet)) - wo;
+        let direction = frame.to_world(&wi);
+        if wi.z <= 0.0 || direction.dot(geometric) <= 0.0 {
+            return None;
+        }
+        Some((direction, wo.dot(&microfacet), self.sample_weight(wo, &wi)))
+    }
+
+    /// Smith masking-shadowing over masking, the weight of a visible normal sample
+    pub fn sample_weight(&self, wo: &Vector3, wi: &Vector3) -> f64 {
+        let lambda_o = self.lambda(wo);
+        (1.0 + lambda_o) / (1.0 + lambda_o + self.lambda(wi))
+    }
+}
@@ -0,0 +1,70 @@
+use crate::{
+    component::hit::HitRecord,
+    math::{self, Vector3},
+    texture::TextureRef,
+};
+
+use super::{Material, MaterialRef, ScatterResult};
+
+/// Blend of two materials, `weight` (its first channel) is the chance of scattering
+/// off `second`, emission is blended by the same amount
+pub struct Mix {
+    first: MaterialRef,
+    second: MaterialRef,
+    weight: TextureRef,
+}
+
+impl Mix {
+    pub fn new(first: MaterialRef, second: MaterialRef, weight: TextureRef) -> Self {
+        Self {
+            first,
+            second,
+            weight,
+        }
+    }
+}
+
+impl Material for Mix {
+    fn scatter(&self, record: HitRecord) -> Option<ScatterResult> {
+        let weight = self.weight.value(&record).x.clamp(0.0, 1.0);
+        if math::rand_f64() < weight {
+            self.second.scatter(record)
+        } else {
+            self.first.scatter(record)
+        }
+    }
+
+    fn eval(&self, record: &HitRecord, direction: &Vector3) -> Option<Vector3> {
+        let weight = self.weight.value(record).x.clamp(0.0, 1.0);
+        match (
+            self.first.eval(record, direction),
+            self.second.eval(record, direction),
+        ) {
+            (None, None) => None,
+            (first, second) => Some(
+                first.unwrap_or(Vector3::fill(0.0)) * (1.0 - weight)
+                    + second.unwrap_or(Vector3::fill(0.0)) * weight,
+            ),
+        }
+    }
+
+    fn is_delta(&self, record: &HitRecord) -> bool {
+        self.first.is_delta(record) || self.second.is_delta(record)
+    }
+
+    fn pdf(&self, record: &HitRecord, direction: &Vector3) -> Option<f64> {
+        let weight = self.weight.value(record).x.clamp(0.0, 1.0);
+        let first = self.first.pdf(record, direction)?;
+        let second = self.second.pdf(record, direction)?;
+        Some(first * (1.0 - weight) + second * weight)
+    }
+
+    fn emit(&self, record: &HitRecord) -> Vector3 {
+        let weight = self.weight.value(record).x.clamp(0.0, 1.0);
+        self.first.emit(record) * (1.0 - weight) + self.second.emit(record) * weight
+    }
+
+    fn emissive(&self) -> bool {
+        self.first.emissive() || self.second.emissive()
+    }
+}
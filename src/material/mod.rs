@@ -1,24 +1,62 @@
+use std::sync::Arc;
+
 use crate::{
     component::{hit::HitRecord, ray::Ray},
     math::Vector3,
 };
 
+pub mod coated;
+pub mod conductor;
 pub mod diffuse_light;
 pub mod glass;
+pub mod henyey_greenstein;
+pub mod isotropic;
 pub mod lambert;
 pub mod metal;
+pub mod microfacet;
+pub mod mix;
+pub mod principled;
+pub mod sheen;
+pub mod subsurface;
+pub mod thin_film;
 
 pub struct ScatterResult {
-    pub _t: f64,
     pub ray: Ray,
     pub attenuation: Vector3,
+    /// The direction only holds for the hero wavelength
+    pub dispersed: bool,
 }
 
 pub trait Material: Send + Sync + 'static {
     fn scatter(&self, _record: HitRecord) -> Option<ScatterResult> {
         None
     }
-    fn emit(&self) -> Vector3 {
+    /// BSDF, or phase function for volumes, times the cosine towards the unit `direction`
+    /// light arrives from. `None` when sampled lights can't light the material.
+    fn eval(&self, _record: &HitRecord, _direction: &Vector3) -> Option<Vector3> {
+        None
+    }
+    /// Density `scatter` picks the unit `direction` with, over the sphere. `None` for
+    /// delta materials, the environment then reaches the hit only by scattering into it.
+    fn pdf(&self, _record: &HitRecord, _direction: &Vector3) -> Option<f64> {
+        None
+    }
+    /// Whether `scatter` can pick directions `eval` leaves out, like mirrors and
+    /// refraction, so light can't be connected to the hit
+    fn is_delta(&self, _record: &HitRecord) -> bool {
+        false
+    }
+    fn emit(&self, _record: &HitRecord) -> Vector3 {
         Vector3::fill(0.0)
     }
+    /// Whether `emit` can return anything, so light can be traced from the surface
+    fn emissive(&self) -> bool {
+        false
+    }
+    /// Phase functions scatter inside volumes, without a surface cosine
+    fn volumetric(&self) -> bool {
+        false
+    }
 }
+
+pub type MaterialRef = Arc<dyn Material>;
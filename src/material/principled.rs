@@ -0,0 +1,236 @@
+use std::f64::consts::PI;
+
+use crate::{
+    component::{hit::HitRecord, ray::Ray},
+    math::{self, Vector3},
+    texture::TextureRef,
+};
+
+use super::{
+    microfacet::{Frame, Ggx},
+    sheen::Sheen,
+    Material, ScatterResult,
+};
+
+/// Disney style layered material. Every call picks one lobe: clearcoat, then metal,
+/// then dielectric specular, transmission or diffuse with sheen, each with the
+/// probability of its share so the chosen lobe keeps an unscaled weight.
+/// The scalar parameters read the first channel of their texture.
+pub struct Principled {
+    pub(crate) base_color: TextureRef,
+    pub(crate) metallic: TextureRef,
+    pub(crate) roughness: TextureRef,
+    /// Dielectric reflectance, `0.5` is the usual 4%
+    pub(crate) specular: TextureRef,
+    pub(crate) clearcoat: TextureRef,
+    pub(crate) clearcoat_roughness: TextureRef,
+    pub(crate) sheen: TextureRef,
+    /// Mix of white and the base color for the sheen
+    pub(crate) sheen_tint: TextureRef,
+    /// Lobe of the sheen, a white one scaled by `sheen` and `sheen_tint`
+    pub(crate) sheen_lobe: Sheen,
+    pub(crate) transmission: TextureRef,
+    pub(crate) refraction_index: f64,
+}
+
+fn schlick(f0: f64, cosine: f64) -> f64 {
+    f0 + (1.0 - f0) * (1.0 - cosine).clamp(0.0, 1.0).powi(5)
+}
+
+impl Principled {
+    fn scalar(texture: &TextureRef, record: &HitRecord) -> f64 {
+        texture.value(record).x.clamp(0.0, 1.0)
+    }
+
+    /// Color of the sheen over the diffuse lobe
+    fn sheen_color(&self, base_color: &Vector3, record: &HitRecord) -> Vector3 {
+        let tint = Self::scalar(&self.sheen_tint, record);
+        (Vector3::fill(1.0 - tint) + base_color * tint) * Self::scalar(&self.sheen, record)
+    }
+
+    /// Rough dielectric interface for the transmissive part, from either side
+    fn transmit(
+        &self,
+        distribution: &Ggx,
+        frame: &Frame,
+        wo: &Vector3,
+        record: &HitRecord,
+        tint: Vector3,
+    ) -> Option<ScatterResult> {
+        let ratio = if record.front {
+            1.0 / self.refraction_index
+        } else {
+            self.refraction_index
+        };
+        let microfacet = frame.to_world(&distribution.sample_visible_normal(wo));
+        let unit_direction = record.direction.normal();
+        let cosine = (-unit_direction.dot(&microfacet)).clamp(-1.0, 1.0);
+        let sine = (1.0 - cosine * cosine).sqrt();
+        let f0 = ((1.0 - ratio) / (1.0 + ratio)).powi(2);
+
+        let reflected = ratio * sine > 1.0 || schlick(f0, cosine) > math::rand_f64();
+        let (direction, attenuation) = if reflected {
+            (unit_direction.reflect(&microfacet), Vector3::fill(1.0))
+        } else {
+            (unit_direction.refract(&microfacet, ratio, cosine), tint)
+        };
+        if (direction.dot(&record.normal) > 0.0) != reflected {
+            return None;
+        }
+
+        let weight = distribution.sample_weight(wo, &frame.to_local(&direction.normal()));
+        Some(ScatterResult {
+            ray: Ray::new(record.point.clone(), direction),
+            attenuation: attenuation * weight,
+            dispersed: false,
+        })
+    }
+}
+
+impl Material for Principled {
+    fn scatter(&self, record: HitRecord) -> Option<ScatterResult> {
+        let frame = Frame::new(&record);
+        let wo = frame.to_local(&-record.direction.normal());
+        if wo.z <= 0.0 {
+            return None;
+        }
+
+        let base_color = self.base_color.value(&record);
+        let roughness = Self::scalar(&self.roughness, &record);
+        let distribution = Ggx::new(roughness, 0.0);
+
+        // only transmitted rays get inside, where the surface acts like glass
+        if !record.front {
+            return self.transmit(&distribution, &frame, &wo, &record, Vector3::fill(1.0));
+        }
+
+        let specular_ray = |distribution: &Ggx, tint: &dyn Fn(f64) -> Vector3| {
+            distribution
+                .sample_reflection(&frame, &wo, &record.normal)
+                .map(|(direction, cosine, weight)| ScatterResult {
+                    ray: Ray::new(record.point.clone(), direction),
+                    attenuation: tint(cosine) * weight,
+                    dispersed: false,
+                })
+        };
+
+        let clearcoat = Self::scalar(&self.clearcoat, &record);
+        if clearcoat > 0.0 {
+            let coat = Ggx::new(Self::scalar(&self.clearcoat_roughness, &record), 0.0);
+            let probability = clearcoat * schlick(0.04, wo.z);
+            if math::rand_f64() < probability {
+                return specular_ray(&coat, &|_| Vector3::fill(1.0));
+            }
+        }
+
+        if math::rand_f64() < Self::scalar(&self.metallic, &record) {
+            return specular_ray(&distribution, &|cosine| {
+                let white = Vector3::fill(1.0);
+                &base_color + (white - &base_color) * (1.0 - cosine).clamp(0.0, 1.0).powi(5)
+            });
+        }
+
+        let f0 = 0.08 * Self::scalar(&self.specular, &record);
+        if math::rand_f64() < schlick(f0, wo.z) {
+            return specular_ray(&distribution, &|_| Vector3::fill(1.0));
+        }
+
+        if math::rand_f64() < Self::scalar(&self.transmission, &record) {
+            return self.transmit(&distribution, &frame, &wo, &record, base_color);
+        }
+
+        let mut direction = &record.shading_normal + Vector3::random_unit();
+        if direction.near_zero() {
+            direction = record.shading_normal.clone();
+        }
+        if direction.dot(&record.normal) <= 0.0 {
+            direction = direction.reflect(&record.normal);
+        }
+
+        let sheen_color = self.sheen_color(&base_color, &record);
+        let attenuation = self.sheen_lobe.layer(
+            base_color,
+            &sheen_color,
+            &-record.direction.normal(),
+            &direction.normal(),
+            &record.shading_normal,
+        );
+        Some(ScatterResult {
+            ray: Ray::new(record.point.clone(), direction),
+            attenuation,
+            dispersed: false,
+        })
+    }
+
+    /// Transmission refracts, and the back side only ever does
+    fn is_delta(&self, record: &HitRecord) -> bool {
+        !record.front || Self::scalar(&self.transmission, record) > 0.0
+    }
+
+    /// The lobes `eval` covers with the chances `scatter` picks them with, `None` with
+    /// transmission, whose rough interface only `scatter` follows
+    fn pdf(&self, record: &HitRecord, direction: &Vector3) -> Option<f64> {
+        if self.is_delta(record) {
+            return None;
+        }
+        let frame = Frame::new(record);
+        let wo = frame.to_local(&-record.direction.normal());
+        let wi = frame.to_local(direction);
+        if wo.z <= 0.0 || wi.z <= 0.0 {
+            return Some(0.0);
+        }
+
+        let specular =
+            Ggx::new(Self::scalar(&self.roughness, record), 0.0).pdf_reflection(&wo, &wi);
+        let clearcoat = Self::scalar(&self.clearcoat, record) * schlick(0.04, wo.z);
+        let coat =
+            Ggx::new(Self::scalar(&self.clearcoat_roughness, record), 0.0).pdf_reflection(&wo, &wi);
+        let metallic = Self::scalar(&self.metallic, record);
+        let reflectance = schlick(0.08 * Self::scalar(&self.specular, record), wo.z);
+
+        let dielectric = reflectance * specular + (1.0 - reflectance) * wi.z / PI;
+        let base = metallic * specular + (1.0 - metallic) * dielectric;
+        Some(clearcoat * coat + (1.0 - clearcoat) * base)
+    }
+
+    /// The lobes weighted by the same chances scatter picks them with
+    fn eval(&self, record: &HitRecord, direction: &Vector3) -> Option<Vector3> {
+        if !record.front || direction.dot(&record.normal) <= 0.0 {
+            return None;
+        }
+        let frame = Frame::new(record);
+        let wo = frame.to_local(&-record.direction.normal());
+        let wi = frame.to_local(direction);
+        if wo.z <= 0.0 || wi.z <= 0.0 {
+            return None;
+        }
+
+        let base_color = self.base_color.value(record);
+        let distribution = Ggx::new(Self::scalar(&self.roughness, record), 0.0);
+        let specular = distribution.eval_reflection(&wo, &wi);
+
+        let clearcoat = Self::scalar(&self.clearcoat, record) * schlick(0.04, wo.z);
+        let coat = Ggx::new(Self::scalar(&self.clearcoat_roughness, record), 0.0)
+            .eval_reflection(&wo, &wi);
+
+        let metallic = Self::scalar(&self.metallic, record);
+        let cosine = wo.dot(&(&wo + &wi).normal()).clamp(0.0, 1.0);
+        let metal =
+            (&base_color + (Vector3::fill(1.0) - &base_color) * (1.0 - cosine).powi(5)) * specular;
+
+        let reflectance = schlick(0.08 * Self::scalar(&self.specular, record), wo.z);
+        let diffuse_share = (1.0 - reflectance) * (1.0 - Self::scalar(&self.transmission, record));
+        let sheen_color = self.sheen_color(&base_color, record);
+        let diffuse = self.sheen_lobe.layer(
+            base_color,
+            &sheen_color,
+            &-record.direction.normal(),
+            direction,
+            &record.shading_normal,
+        ) * (wi.z / PI);
+
+        let dielectric = Vector3::fill(reflectance * specular) + diffuse * diffuse_share;
+        let base = metal * metallic + dielectric * (1.0 - metallic);
+        Some(Vector3::fill(clearcoat * coat) + base * (1.0 - clearcoat))
+    }
+}
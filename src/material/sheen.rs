@@ -0,0 +1,104 @@
+use std::{f64::consts::PI, sync::OnceLock};
+
+use serde::Deserialize;
+
+use crate::math::Vector3;
+
+/// Entries of the directional albedo table, over the cosine of the view direction
+const ALBEDO_ENTRIES: usize = 32;
+/// Cosine weighted directions per side of the grid integrating every entry
+const ALBEDO_GRID: usize = 32;
+
+/// Retro-reflective fuzz of cloth and velvet, the "Charlie" distribution of Estevez and Kulla
+#[derive(Clone, Debug, Deserialize)]
+pub struct Sheen {
+    color: Vector3,
+    #[serde(default = "default_roughness")]
+    roughness: f64,
+    /// Reflectance of a white sheen for every view angle, filled on first use
+    #[serde(skip)]
+    albedo: OnceLock<Vec<f64>>,
+}
+
+fn default_roughness() -> f64 {
+    0.5
+}
+
+impl Sheen {
+    pub fn new(color: Vector3, roughness: f64) -> Self {
+        Self {
+            color,
+            roughness,
+            albedo: OnceLock::new(),
+        }
+    }
+
+    /// Weight of a cosine weighted sample of a diffuse base under the sheen, with the
+    /// sheen color scaled by `tint`. The base loses the light the sheen reflects, so the
+    /// two together never exceed the base alone by more than the sheen.
+    pub fn layer(
+        &self,
+        diffuse: Vector3,
+        tint: &Vector3,
+        wo: &Vector3,
+        wi: &Vector3,
+        normal: &Vector3,
+    ) -> Vector3 {
+        let color = &self.color * tint;
+        let strongest = color.x.max(color.y).max(color.z);
+        let scale = 1.0 - strongest * self.albedo(wo.dot(normal));
+        diffuse * scale.max(0.0) + color * self.weight(wo, wi, normal)
+    }
+
+    /// BRDF of a white sheen times pi
+    fn weight(&self, wo: &Vector3, wi: &Vector3, normal: &Vector3) -> f64 {
+        let (cos_o, cos_i) = (wo.dot(normal), wi.dot(normal));
+        if cos_o <= 0.0 || cos_i <= 0.0 {
+            return 0.0;
+        }
+        let half = (wo + wi).normal();
+        let cos_h = half.dot(normal).clamp(0.0, 1.0);
+        let sin_h = (1.0 - cos_h * cos_h).sqrt();
+
+        let inverse = 1.0 / self.roughness.clamp(0.07, 1.0);
+        let distribution = (2.0 + inverse) * sin_h.powf(inverse) / (2.0 * PI);
+        // Neubelt and Pettineo visibility
+        let visibility = 1.0 / (4.0 * (cos_i + cos_o - cos_i * cos_o));
+        distribution * visibility * PI
+    }
+
+    /// Share of the light arriving along a direction with cosine `cos_o` that a white
+    /// sheen reflects
+    fn albedo(&self, cos_o: f64) -> f64 {
+        let table = self.albedo.get_or_init(|| {
+            (0..ALBEDO_ENTRIES)
+                .map(|entry| self.integrate((entry as f64 + 0.5) / ALBEDO_ENTRIES as f64))
+                .collect()
+        });
+        let position = (cos_o.clamp(0.0, 1.0) * ALBEDO_ENTRIES as f64 - 0.5)
+            .clamp(0.0, (ALBEDO_ENTRIES - 1) as f64);
+        let below = (position as usize).min(ALBEDO_ENTRIES - 2);
+        let fraction = position - below as f64;
+        table[below] * (1.0 - fraction) + table[below + 1] * fraction
+    }
+
+    /// Directional albedo on a grid of cosine weighted directions
+    fn integrate(&self, cos_o: f64) -> f64 {
+        let normal = Vector3::new(0.0, 0.0, 1.0);
+        let wo = Vector3::new((1.0 - cos_o * cos_o).sqrt(), 0.0, cos_o);
+        let mut total = 0.0;
+        for i in 0..ALBEDO_GRID {
+            for j in 0..ALBEDO_GRID {
+                let radius = ((i as f64 + 0.5) / ALBEDO_GRID as f64).sqrt();
+                let angle = 2.0 * PI * (j as f64 + 0.5) / ALBEDO_GRID as f64;
+                let wi = Vector3::new(
+                    radius * angle.cos(),
+                    radius * angle.sin(),
+                    (1.0 - radius * radius).sqrt(),
+                );
+                total += self.weight(&wo, &wi, &normal);
+            }
+        }
+        total / (ALBEDO_GRID * ALBEDO_GRID) as f64
+    }
+}
@@ -0,0 +1,89 @@
+use crate::{
+    component::hit::HitRecord,
+    math::{self, Vector3},
+};
+
+use super::{glass::Glass, henyey_greenstein::HenyeyGreenstein, Material, ScatterResult};
+
+/// Translucent material for skin, wax or marble. Light refracts through a smooth
+/// boundary and random walks inside, scattering like a participating medium until
+/// it reaches the boundary again. Each walk step uses one bounce of `max_depth`.
+pub struct Subsurface {
+    /// Single scattering albedo per channel
+    albedo: Vector3,
+    /// Extinction coefficient per channel, the inverse of the mean free path
+    extinction: [f64; 3],
+    boundary: Glass,
+    phase_function: HenyeyGreenstein,
+}
+
+impl Subsurface {
+    pub fn new(
+        albedo: Vector3,
+        mean_free_path: Vector3,
+        refraction_index: f64,
+        anisotropy: f64,
+    ) -> Self {
+        Self {
+            albedo,
+            extinction: [mean_free_path.x, mean_free_path.y, mean_free_path.z]
+                .map(|length| 1.0 / length.max(1e-6)),
+            boundary: Glass::new(refraction_index),
+            phase_function: HenyeyGreenstein::new(Vector3::fill(1.0), anisotropy),
+        }
+    }
+
+    fn transmittance(&self, distance: f64) -> [f64; 3] {
+        self.extinction.map(|sigma| (-sigma * distance).exp())
+    }
+}
+
+impl Material for Subsurface {
+    fn is_delta(&self, _record: &HitRecord) -> bool {
+        true
+    }
+
+    fn scatter(&self, record: HitRecord) -> Option<ScatterResult> {
+        if record.front {
+            return self.boundary.scatter(record);
+        }
+
+        // the ray travelled inside up to this hit, the flight distance is sampled
+        // with a randomly picked channel and weighted by the average over all three
+        let length = record.direction.len();
+        let distance = record.t * length;
+        let sigma = self.extinction[(math::rand_f64() * 3.0) as usize % 3];
+        let flight = -(1.0 - math::rand_f64()).ln() / sigma;
+
+        if flight < distance {
+            let transmittance = self.transmittance(flight);
+            let pdf = (0..3)
+                .map(|c| self.extinction[c] * transmittance[c])
+                .sum::<f64>()
+                / 3.0;
+            let weight = Vector3::new(
+                self.extinction[0] * transmittance[0],
+                self.extinction[1] * transmittance[1],
+                self.extinction[2] * transmittance[2],
+            ) / pdf;
+
+            let direction = &record.direction / length;
+            let point = &record.point - &direction * (distance - flight);
+            let mut scattered = self.phase_function.scatter(HitRecord {
+                point,
+                direction,
+                ..record
+            })?;
+            scattered.attenuation = weight * &self.albedo;
+            return Some(scattered);
+        }
+
+        let transmittance = self.transmittance(distance);
+        let probability = transmittance.iter().sum::<f64>() / 3.0;
+        let weight =
+            Vector3::new(transmittance[0], transmittance[1], transmittance[2]) / probability;
+        let mut scattered = self.boundary.scatter(record)?;
+        scattered.attenuation *= weight;
+        Some(scattered)
+    }
+}
@@ -0,0 +1,76 @@
+use std::f64::consts::PI;
+
+use serde::Deserialize;
+
+use crate::math::Vector3;
+
+use super::conductor::Conductor;
+
+/// Wavelengths in nanometers standing in for the red, green and blue channels
+const CHANNEL_WAVELENGTHS: [f64; 3] = [650.0, 532.0, 450.0];
+
+/// What the film lies on
+pub enum Substrate<'a> {
+    Dielectric(f64),
+    Conductor { eta: &'a Vector3, k: &'a Vector3 },
+}
+
+/// Interference coating like a soap film or an oil slick, `thickness` in nanometers.
+/// The reflectance is evaluated at one wavelength per RGB channel.
+#[derive(Clone, Debug, Deserialize)]
+pub struct ThinFilm {
+    thickness: f64,
+    refraction_index: f64,
+}
+
+impl ThinFilm {
+    /// Airy reflectance, averaged over both polarizations, for light arriving with
+    /// `cos_incident` from a medium of index `outside`
+    pub fn reflectance(&self, cos_incident: f64, outside: f64, substrate: Substrate) -> Vector3 {
+        let film = self.refraction_index;
+        let cos_incident = cos_incident.clamp(0.0, 1.0);
+        let sin2_film = (outside / film).powi(2) * (1.0 - cos_incident * cos_incident);
+        if sin2_film >= 1.0 {
+            return Vector3::fill(1.0);
+        }
+        let cos_film = (1.0 - sin2_film).sqrt();
+
+        let top_s =
+            (outside * cos_incident - film * cos_film) / (outside * cos_incident + film * cos_film);
+        let top_p =
+            (film * cos_incident - outside * cos_film) / (film * cos_incident + outside * cos_film);
+
+        let [r, g, b] = [0, 1, 2].map(|channel| {
+            let (bottom_s, bottom_p) = match &substrate {
+                Substrate::Dielectric(index) => {
+                    let sin2 = (film / index).powi(2) * sin2_film;
+                    if sin2 >= 1.0 {
+                        (1.0, 1.0)
+                    } else {
+                        let cos = (1.0 - sin2).sqrt();
+                        (
+                            (film * cos_film - index * cos) / (film * cos_film + index * cos),
+                            (index * cos_film - film * cos) / (index * cos_film + film * cos),
+                        )
+                    }
+                }
+                // the phase shift of a metal is close to that of a denser dielectric
+                Substrate::Conductor { eta, k } => {
+                    let [eta, k] = [eta, k].map(|v| [v.x, v.y, v.z][channel] / film);
+                    let amplitude = -Conductor::fresnel(cos_film, eta, k).sqrt();
+                    (amplitude, amplitude)
+                }
+            };
+
+            let phase = 4.0 * PI * film * self.thickness * cos_film / CHANNEL_WAVELENGTHS[channel];
+            let airy = |top: f64, bottom: f64| {
+                let cross = 2.0 * top * bottom * phase.cos();
+                ((top * top + bottom * bottom + cross)
+                    / (1.0 + top * top * bottom * bottom + cross))
+                    .clamp(0.0, 1.0)
+            };
+            0.5 * (airy(top_s, bottom_s) + airy(top_p, bottom_p))
+        });
+        Vector3::new(r, g, b)
+    }
+}
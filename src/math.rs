@@ -13,6 +13,32 @@ pub fn rand_range_f64(min: f64, max: f64) -> f64 {
     rng.random_range(min..max)
 }
 
+#[derive(Clone, Copy, Debug)]
+pub struct Interval {
+    pub min: f64,
+    pub max: f64,
+}
+
+impl Interval {
+    pub const fn new(min: f64, max: f64) -> Self {
+        Self { min, max }
+    }
+
+    pub const fn universe() -> Self {
+        Self::new(f64::NEG_INFINITY, f64::INFINITY)
+    }
+
+    #[inline]
+    pub fn surrounds(&self, t: f64) -> bool {
+        self.min < t && t < self.max
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.min >= self.max
+    }
+}
+
 #[derive(Clone, Debug, Deserialize)]
 pub struct Vector3 {
     pub x: f64,
@@ -99,6 +125,22 @@ impl Vector3 {
         self / self.len()
     }
 
+    /// Two unit vectors that form an orthonormal basis with this (unit) vector
+    pub fn orthonormal_basis(&self) -> (Self, Self) {
+        let sign = 1.0_f64.copysign(self.z);
+        let a = -1.0 / (sign + self.z);
+        let b = self.x * self.y * a;
+        (
+            Self::new(1.0 + sign * self.x * self.x * a, sign * b, -sign * self.x),
+            Self::new(b, sign + self.y * self.y * a, -self.y),
+        )
+    }
+
+    /// Brightness of a linear sRGB color
+    pub fn luminance(&self) -> f64 {
+        0.2126 * self.x + 0.7152 * self.y + 0.0722 * self.z
+    }
+
     pub fn near_zero(&self) -> bool {
         let s = 1e-8;
         (self.x.abs() < s) && (self.y.abs() < s) && (self.z.abs() < s)
@@ -113,6 +155,99 @@ impl Vector3 {
     }
 }
 
+/// Affine transform, row major
+#[derive(Clone, Debug)]
+pub struct Matrix4 {
+    m: [[f64; 4]; 4],
+}
+
+impl Matrix4 {
+    pub fn identity() -> Self {
+        let mut m = [[0.0; 4]; 4];
+        (0..4).for_each(|i| m[i][i] = 1.0);
+        Self { m }
+    }
+
+    pub fn from_columns(columns: [[f32; 4]; 4]) -> Self {
+        let mut m = [[0.0; 4]; 4];
+        for (c, column) in columns.iter().enumerate() {
+            for (r, value) in column.iter().enumerate() {
+                m[r][c] = *value as f64;
+            }
+        }
+        Self { m }
+    }
+
+    pub fn transpose(&self) -> Self {
+        let mut m = [[0.0; 4]; 4];
+        for (r, row) in m.iter_mut().enumerate() {
+            for (c, value) in row.iter_mut().enumerate() {
+                *value = self.m[c][r];
+            }
+        }
+        Self { m }
+    }
+
+    /// Gauss-Jordan elimination, `None` for singular matrices
+    pub fn inverse(&self) -> Option<Self> {
+        let mut a = self.m;
+        let mut inv = Self::identity().m;
+        for col in 0..4 {
+            let pivot = (col..4).max_by(|x, y| a[*x][col].abs().total_cmp(&a[*y][col].abs()))?;
+            if a[pivot][col].abs() < 1e-12 {
+                return None;
+            }
+            a.swap(col, pivot);
+            inv.swap(col, pivot);
+
+            let scale = 1.0 / a[col][col];
+            for c in 0..4 {
+                a[col][c] *= scale;
+                inv[col][c] *= scale;
+            }
+            for row in (0..4).filter(|r| *r != col) {
+                let factor = a[row][col];
+                for c in 0..4 {
+                    a[row][c] -= factor * a[col][c];
+                    inv[row][c] -= factor * inv[col][c];
+                }
+            }
+        }
+        Some(Self { m: inv })
+    }
+
+    pub fn transform_point(&self, p: &Vector3) -> Vector3 {
+        let m = &self.m;
+        Vector3::new(
+            m[0][0] * p.x + m[0][1] * p.y + m[0][2] * p.z + m[0][3],
+            m[1][0] * p.x + m[1][1] * p.y + m[1][2] * p.z + m[1][3],
+            m[2][0] * p.x + m[2][1] * p.y + m[2][2] * p.z + m[2][3],
+        )
+    }
+
+    pub fn transform_vector(&self, v: &Vector3) -> Vector3 {
+        let m = &self.m;
+        Vector3::new(
+            m[0][0] * v.x + m[0][1] * v.y + m[0][2] * v.z,
+            m[1][0] * v.x + m[1][1] * v.y + m[1][2] * v.z,
+            m[2][0] * v.x + m[2][1] * v.y + m[2][2] * v.z,
+        )
+    }
+}
+
+impl ops::Mul for &Matrix4 {
+    type Output = Matrix4;
+    fn mul(self, rhs: Self) -> Self::Output {
+        let mut m = [[0.0; 4]; 4];
+        for (r, row) in m.iter_mut().enumerate() {
+            for (c, value) in row.iter_mut().enumerate() {
+                *value = (0..4).map(|k| self.m[r][k] * rhs.m[k][c]).sum();
+            }
+        }
+        Matrix4 { m }
+    }
+}
+
 macro_rules! impl_math_vec3 {
     (impl $tr:ident & $tra:ident as $f:ident & $fa:ident & $op:tt) => {
         impl_math_generic!(impl $tr & $tra for Vector3 as $f & $fa & $op use |a, b| {
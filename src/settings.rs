@@ -1,13 +1,82 @@
 use config::{Config, ConfigError, File};
 use serde::Deserialize;
 
-use crate::math::Vector3;
+use crate::{description::background::BackgroundDescription, math::Vector3};
 
 #[derive(Debug, Deserialize)]
 pub(crate) struct Image {
     pub(crate) output: String,
     pub(crate) pixel_samples: u32,
     pub(crate) max_depth: u32,
+    /// Trace wavelengths instead of RGB, needed for dispersion
+    #[serde(default)]
+    pub(crate) spectral: bool,
+    #[serde(default)]
+    pub(crate) light_sampler: LightSampling,
+    #[serde(default)]
+    pub(crate) integrator: Integrator,
+    /// Photons traced per pass of the `sppm` integrator
+    #[serde(default = "default_photons")]
+    pub(crate) photons: u32,
+    /// Starting gather radius of the `sppm` integrator, a few pixels wide at the focus
+    /// distance when missing
+    pub(crate) photon_radius: Option<f64>,
+    /// Distance the `ambient_occlusion` integrator looks for blockers within
+    #[serde(default = "default_ao_radius")]
+    pub(crate) ao_radius: f64,
+}
+
+fn default_photons() -> u32 {
+    100_000
+}
+
+fn default_ao_radius() -> f64 {
+    1.0
+}
+
+/// How the light reaching every pixel sample is estimated
+#[derive(Debug, Default, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum Integrator {
+    /// Paths traced from the camera
+    #[default]
+    Path,
+    /// Paths from the camera and from the lights connected at every vertex, converges
+    /// caustics seen on diffuse surfaces
+    Bdpt,
+    /// Stochastic progressive photon mapping, one pass of photons per pixel sample. Media
+    /// store no photons and sample the lights directly, missing the caustics inside them
+    Sppm,
+    /// White where nothing blocks the hemisphere within `ao_radius`
+    AmbientOcclusion,
+    /// Shading normals facing out of the surface, mapped from -1..1 to 0..1
+    Normals,
+    /// Color a single bounce off the first surface keeps
+    Albedo,
+    /// White at the lens, black from twice the focus distance on
+    Depth,
+    /// Texture coordinates in red and green
+    Uv,
+    /// Weights of the triangle corners in red, green and blue, black off meshes
+    Barycentrics,
+    /// A color per material, numbered in scene order so every run matches
+    MaterialId,
+    /// Bounces before the path leaves the scene or ends, blue for none to red for
+    /// `max_depth`
+    RayDepth,
+}
+
+/// How the lights of the scene are sampled at every bounce
+#[derive(Debug, Default, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum LightSampling {
+    /// Every light, best for a handful of them
+    #[default]
+    All,
+    /// One light picked in proportion to its power
+    Power,
+    /// One light picked by its estimated contribution, nearer lights more often
+    Bvh,
 }
 
 #[derive(Debug, Deserialize)]
@@ -25,7 +94,11 @@ pub(crate) struct Camera {
 
 #[derive(Debug, Deserialize)]
 pub(crate) struct Scene {
+    /// Scene file, the random scene when empty
+    #[serde(default)]
     pub(crate) input: String,
+    /// Replaces the background of the scene
+    pub(crate) background: Option<BackgroundDescription>,
 }
 
 #[derive(Debug, Deserialize)]
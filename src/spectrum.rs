@@ -0,0 +1,113 @@
+use std::sync::OnceLock;
+
+use crate::math::{self, Vector3};
+
+/// Visible range that the hero wavelengths are drawn from, in nanometers
+const LAMBDA_MIN: f64 = 380.0;
+const LAMBDA_MAX: f64 = 730.0;
+const LAMBDA_RANGE: f64 = LAMBDA_MAX - LAMBDA_MIN;
+
+/// A uniform hero wavelength and two more rotated by a third of the range,
+/// so every one of them is uniformly distributed on its own
+pub fn sample_wavelengths() -> [f64; 3] {
+    wavelengths_at(math::rand_f64())
+}
+
+/// Hero wavelength drawn from the `stratum` of `count` equal parts of the range, so a
+/// few samples shared by a whole image still cover the spectrum evenly
+pub fn sample_wavelengths_stratified(stratum: u32, count: u32) -> [f64; 3] {
+    wavelengths_at((stratum as f64 + math::rand_f64()) / count as f64)
+}
+
+/// `fraction` of the way through the range for the hero
+fn wavelengths_at(fraction: f64) -> [f64; 3] {
+    let hero = fraction * LAMBDA_RANGE;
+    [0.0, 1.0, 2.0].map(|i| LAMBDA_MIN + (hero + i * LAMBDA_RANGE / 3.0) % LAMBDA_RANGE)
+}
+
+/// Smooth reflectance spectrum of an RGB color, the three basis functions add up
+/// to one so white stays flat and nothing goes above the largest channel
+fn upsample_at(rgb: &Vector3, lambda: f64) -> f64 {
+    let blue = 1.0 / (1.0 + ((lambda - 490.0) / 10.0).exp());
+    let red = 1.0 / (1.0 + ((590.0 - lambda) / 10.0).exp());
+    let green = (1.0 - blue - red).max(0.0);
+    rgb.x * red + rgb.y * green + rgb.z * blue
+}
+
+/// Values of an RGB color at each of the `wavelengths`
+pub fn upsample(rgb: &Vector3, wavelengths: &[f64; 3]) -> Vector3 {
+    let [a, b, c] = wavelengths.map(|lambda| upsample_at(rgb, lambda));
+    Vector3::new(a, b, c)
+}
+
+/// RGB colors become their values at the traced `wavelengths`, if any
+pub fn upsample_traced(rgb: Vector3, wavelengths: Option<&[f64; 3]>) -> Vector3 {
+    match wavelengths {
+        Some(wavelengths) => upsample(&rgb, wavelengths),
+        None => rgb,
+    }
+}
+
+fn lobe(lambda: f64, mean: f64, below: f64, above: f64) -> f64 {
+    let sigma = if lambda < mean { below } else { above };
+    (-0.5 * ((lambda - mean) / sigma).powi(2)).exp()
+}
+
+/// CIE 1931 color matching functions, multi-lobe fit by Wyman, Sloan and Shirley
+fn color_matching(lambda: f64) -> Vector3 {
+    Vector3::new(
+        1.056 * lobe(lambda, 599.8, 37.9, 31.0) + 0.362 * lobe(lambda, 442.0, 16.0, 26.7)
+            - 0.065 * lobe(lambda, 501.1, 20.4, 26.2),
+        0.821 * lobe(lambda, 568.8, 46.9, 40.5) + 0.286 * lobe(lambda, 530.9, 16.3, 31.1),
+        1.217 * lobe(lambda, 437.0, 11.8, 36.0) + 0.681 * lobe(lambda, 459.0, 26.0, 13.8),
+    )
+}
+
+pub fn xyz_to_linear_srgb(xyz: &Vector3) -> Vector3 {
+    Vector3::new(
+        3.2406 * xyz.x - 1.5372 * xyz.y - 0.4986 * xyz.z,
+        -0.9689 * xyz.x + 1.8758 * xyz.y + 0.0415 * xyz.z,
+        0.0557 * xyz.x - 0.2040 * xyz.y + 1.0570 * xyz.z,
+    )
+}
+
+/// RGB of the flat spectrum, scaled to white so untinted light keeps its color
+fn white() -> &'static Vector3 {
+    static WHITE: OnceLock<Vector3> = OnceLock::new();
+    WHITE.get_or_init(|| {
+        let xyz = (0..LAMBDA_RANGE as usize).fold(Vector3::fill(0.0), |acc, i| {
+            acc + color_matching(LAMBDA_MIN + i as f64 + 0.5)
+        });
+        xyz_to_linear_srgb(&xyz)
+    })
+}
+
+/// Linear RGB color of a black body at `temperature` Kelvin, with a luminance of one
+pub fn blackbody(temperature: f64) -> Vector3 {
+    // Planck's law, constants for wavelengths in meters
+    const H: f64 = 6.626_070_15e-34;
+    const C: f64 = 299_792_458.0;
+    const K: f64 = 1.380_649e-23;
+    let radiance = |nanometers: f64| {
+        let lambda = nanometers * 1e-9;
+        2.0 * H * C * C / (lambda.powi(5) * ((H * C / (lambda * K * temperature)).exp() - 1.0))
+    };
+
+    let xyz = (0..LAMBDA_RANGE as usize).fold(Vector3::fill(0.0), |acc, i| {
+        let lambda = LAMBDA_MIN + i as f64 + 0.5;
+        acc + color_matching(lambda) * radiance(lambda)
+    });
+    // balanced like the traced spectra, so the flat spectrum is white
+    let rgb = xyz_to_linear_srgb(&xyz) / white();
+    let rgb = Vector3::new(rgb.x.max(0.0), rgb.y.max(0.0), rgb.z.max(0.0));
+    let luminance = rgb.luminance();
+    rgb / luminance
+}
+
+/// Linear RGB estimate from the radiance carried at each of the `wavelengths`
+pub fn to_rgb(values: &Vector3, wavelengths: &[f64; 3]) -> Vector3 {
+    let xyz = color_matching(wavelengths[0]) * values.x
+        + color_matching(wavelengths[1]) * values.y
+        + color_matching(wavelengths[2]) * values.z;
+    xyz_to_linear_srgb(&(xyz * (LAMBDA_RANGE / 3.0))) / white()
+}
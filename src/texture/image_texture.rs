@@ -0,0 +1,66 @@
+use image::{ImageResult, Rgb32FImage};
+
+use crate::{component::hit::HitRecord, math::Vector3};
+
+use super::Texture;
+
+/// Image mapped over the surface UV coordinates, clamped at the edges
+pub struct ImageTexture {
+    image: Rgb32FImage,
+}
+
+impl ImageTexture {
+    pub fn open(file: &str) -> ImageResult<Self> {
+        Ok(Self::from_image(image::open(file)?.into_rgb32f()))
+    }
+
+    /// For data that is not a color, like normal maps
+    pub fn open_linear(file: &str) -> ImageResult<Self> {
+        Ok(Self::from_linear_image(image::open(file)?.into_rgb32f()))
+    }
+
+    /// Alpha channel in all three channels, opaque without one
+    pub fn open_alpha(file: &str) -> ImageResult<Self> {
+        let image = image::open(file)?.into_rgba32f();
+        let (width, height) = image.dimensions();
+        let alpha = image.pixels().flat_map(|p| [p.0[3]; 3]).collect();
+        Ok(Self::from_linear_image(
+            Rgb32FImage::from_raw(width, height, alpha).expect("same dimensions"),
+        ))
+    }
+
+    pub fn from_linear_image(image: Rgb32FImage) -> Self {
+        Self { image }
+    }
+
+    pub fn from_image(mut image: Rgb32FImage) -> Self {
+        // undo the gamma 2 encoding, the same one used on output
+        image
+            .pixels_mut()
+            .for_each(|p| p.0.iter_mut().for_each(|c| *c *= *c));
+        Self { image }
+    }
+
+    /// Multiplies every pixel by a color
+    pub fn scaled(mut self, factor: &Vector3) -> Self {
+        let factor = [factor.x as f32, factor.y as f32, factor.z as f32];
+        self.image
+            .pixels_mut()
+            .for_each(|p| p.0.iter_mut().zip(factor).for_each(|(c, f)| *c *= f));
+        self
+    }
+}
+
+impl Texture for ImageTexture {
+    fn value(&self, record: &HitRecord) -> Vector3 {
+        let (width, height) = self.image.dimensions();
+        let u = record.u.clamp(0.0, 1.0);
+        // image rows go top to bottom
+        let v = 1.0 - record.v.clamp(0.0, 1.0);
+
+        let x = ((u * width as f64) as u32).min(width - 1);
+        let y = ((v * height as f64) as u32).min(height - 1);
+        let [r, g, b] = self.image.get_pixel(x, y).0;
+        Vector3::new(r as f64, g as f64, b as f64)
+    }
+}
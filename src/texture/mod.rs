@@ -0,0 +1,13 @@
+use std::sync::Arc;
+
+use crate::{component::hit::HitRecord, math::Vector3};
+
+pub mod image_texture;
+pub mod solid_color;
+pub mod vertex_color;
+
+pub trait Texture: Send + Sync + 'static {
+    fn value(&self, record: &HitRecord) -> Vector3;
+}
+
+pub type TextureRef = Arc<dyn Texture>;
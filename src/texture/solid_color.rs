@@ -0,0 +1,19 @@
+use crate::{component::hit::HitRecord, math::Vector3};
+
+use super::Texture;
+
+pub struct SolidColor {
+    color: Vector3,
+}
+
+impl SolidColor {
+    pub fn new(color: Vector3) -> Self {
+        Self { color }
+    }
+}
+
+impl Texture for SolidColor {
+    fn value(&self, _record: &HitRecord) -> Vector3 {
+        self.color.clone()
+    }
+}
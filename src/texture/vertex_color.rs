@@ -0,0 +1,23 @@
+use crate::{component::hit::HitRecord, math::Vector3};
+
+use super::Texture;
+
+/// Color interpolated from the mesh vertices, or `fallback` for surfaces without one
+pub struct VertexColor {
+    fallback: Vector3,
+}
+
+impl VertexColor {
+    pub fn new(fallback: Vector3) -> Self {
+        Self { fallback }
+    }
+}
+
+impl Texture for VertexColor {
+    fn value(&self, record: &HitRecord) -> Vector3 {
+        record
+            .vertex_color
+            .clone()
+            .unwrap_or_else(|| self.fallback.clone())
+    }
+}